@@ -0,0 +1,2 @@
+ALTER TABLE answers DROP COLUMN IF EXISTS author_id;
+ALTER TABLE accounts DROP CONSTRAINT IF EXISTS accounts_id_key;
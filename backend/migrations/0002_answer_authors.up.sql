@@ -0,0 +1,4 @@
+ALTER TABLE accounts ADD CONSTRAINT accounts_id_key UNIQUE (id);
+
+ALTER TABLE answers
+    ADD COLUMN IF NOT EXISTS author_id integer REFERENCES accounts (id) ON DELETE SET NULL;
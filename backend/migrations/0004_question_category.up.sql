@@ -0,0 +1,4 @@
+ALTER TABLE questions
+    ADD COLUMN IF NOT EXISTS category TEXT NOT NULL DEFAULT 'general'
+    CONSTRAINT questions_category_check
+    CHECK (category IN ('general', 'debugging', 'concept', 'tooling'));
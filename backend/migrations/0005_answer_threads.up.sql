@@ -0,0 +1,2 @@
+ALTER TABLE answers
+    ADD COLUMN IF NOT EXISTS parent_answer_id integer REFERENCES answers (id) ON DELETE CASCADE;
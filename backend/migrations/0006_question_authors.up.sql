@@ -0,0 +1,2 @@
+ALTER TABLE questions
+    ADD COLUMN IF NOT EXISTS author_id integer REFERENCES accounts (id) ON DELETE SET NULL;
@@ -0,0 +1,6 @@
+CREATE TABLE IF NOT EXISTS answer_votes (
+    answer_id integer NOT NULL REFERENCES answers (id) ON DELETE CASCADE,
+    account_id integer NOT NULL REFERENCES accounts (id) ON DELETE CASCADE,
+    value smallint NOT NULL CHECK (value IN (-1, 1)),
+    UNIQUE (answer_id, account_id)
+);
@@ -0,0 +1 @@
+ALTER TABLE questions DROP CONSTRAINT IF EXISTS questions_tags_not_empty;
@@ -0,0 +1,4 @@
+UPDATE questions SET tags = NULL WHERE cardinality(tags) = 0;
+
+ALTER TABLE questions
+    ADD CONSTRAINT questions_tags_not_empty CHECK (tags IS NULL OR cardinality(tags) > 0);
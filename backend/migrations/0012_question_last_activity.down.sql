@@ -0,0 +1,2 @@
+DROP INDEX IF EXISTS questions_last_activity_idx;
+ALTER TABLE questions DROP COLUMN IF EXISTS last_activity_at;
@@ -0,0 +1,10 @@
+ALTER TABLE questions
+    ADD COLUMN IF NOT EXISTS last_activity_at TIMESTAMP NOT NULL DEFAULT NOW();
+
+UPDATE questions q SET last_activity_at = GREATEST(
+    q.created_on,
+    COALESCE((SELECT MAX(edited_at) FROM question_revisions r WHERE r.question_id = q.id), q.created_on),
+    COALESCE((SELECT MAX(created_on) FROM answers a WHERE a.corresponding_question = q.id), q.created_on)
+);
+
+CREATE INDEX IF NOT EXISTS questions_last_activity_idx ON questions (last_activity_at DESC, id DESC);
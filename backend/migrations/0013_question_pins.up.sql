@@ -0,0 +1,2 @@
+ALTER TABLE questions
+    ADD COLUMN IF NOT EXISTS pinned BOOLEAN NOT NULL DEFAULT false;
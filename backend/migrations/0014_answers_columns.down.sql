@@ -0,0 +1,8 @@
+ALTER TABLE answers RENAME COLUMN created_at TO created_on;
+
+DROP INDEX IF EXISTS answers_corresponding_question_idx;
+ALTER TABLE answers DROP CONSTRAINT IF EXISTS answers_corresponding_question_fkey;
+ALTER TABLE answers
+    ADD CONSTRAINT answers_corresponding_question_fkey
+    FOREIGN KEY (corresponding_question) REFERENCES questions (id);
+ALTER TABLE answers ALTER COLUMN corresponding_question DROP NOT NULL;
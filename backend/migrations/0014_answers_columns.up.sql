@@ -0,0 +1,13 @@
+-- Spell out the answers table the code relies on: every answer belongs to a question and goes with it,
+-- and carries when it was written as created_at like the newer tables
+
+-- Answers without a question can not be reached through the API, so there is nothing to keep
+DELETE FROM answers WHERE corresponding_question IS NULL;
+ALTER TABLE answers ALTER COLUMN corresponding_question SET NOT NULL;
+ALTER TABLE answers DROP CONSTRAINT IF EXISTS answers_corresponding_question_fkey;
+ALTER TABLE answers
+    ADD CONSTRAINT answers_corresponding_question_fkey
+    FOREIGN KEY (corresponding_question) REFERENCES questions (id) ON DELETE CASCADE;
+CREATE INDEX IF NOT EXISTS answers_corresponding_question_idx ON answers (corresponding_question);
+
+ALTER TABLE answers RENAME COLUMN created_on TO created_at;
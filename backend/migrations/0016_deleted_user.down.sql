@@ -0,0 +1 @@
+DELETE FROM accounts WHERE email = 'deleted-user@invalid';
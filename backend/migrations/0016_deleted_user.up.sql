@@ -0,0 +1,4 @@
+-- Placeholder account the questions and answers of a deleted account are handed to.
+-- It has no row in passwords, so nobody can log in as it
+INSERT INTO accounts (email, password) VALUES ('deleted-user@invalid', '')
+ON CONFLICT (email) DO NOTHING;
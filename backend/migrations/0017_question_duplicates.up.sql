@@ -0,0 +1,3 @@
+ALTER TABLE questions
+    ADD COLUMN IF NOT EXISTS duplicate_of integer REFERENCES questions (id) ON DELETE SET NULL,
+    ADD CONSTRAINT questions_duplicate_of_self CHECK (duplicate_of <> id);
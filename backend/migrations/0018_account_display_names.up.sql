@@ -0,0 +1,4 @@
+ALTER TABLE accounts
+    ADD COLUMN IF NOT EXISTS display_name VARCHAR (50);
+
+UPDATE accounts SET display_name = 'Deleted user' WHERE email = 'deleted-user@invalid';
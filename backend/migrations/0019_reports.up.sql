@@ -0,0 +1,10 @@
+-- A report flags a question or an answer for an admin to look at, exactly one of the two is set
+CREATE TABLE IF NOT EXISTS reports (
+    id serial PRIMARY KEY,
+    question_id integer REFERENCES questions (id) ON DELETE CASCADE,
+    answer_id integer REFERENCES answers (id) ON DELETE CASCADE,
+    reporter_id integer REFERENCES accounts (id) ON DELETE SET NULL,
+    reason TEXT NOT NULL,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    CHECK ((question_id IS NULL) <> (answer_id IS NULL))
+);
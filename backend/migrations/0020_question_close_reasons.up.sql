@@ -0,0 +1,5 @@
+-- A question is closed once it has a close reason, closed questions take no new answers
+ALTER TABLE questions
+    ADD COLUMN IF NOT EXISTS close_reason TEXT
+    CONSTRAINT questions_close_reason_check
+    CHECK (close_reason IN ('duplicate', 'off-topic', 'unclear', 'resolved'));
@@ -0,0 +1,20 @@
+-- An append only log of what was created, updated and deleted, by whom, written in the same
+-- transaction as the change itself
+CREATE TABLE IF NOT EXISTS events (
+    id bigserial PRIMARY KEY,
+    entity_type TEXT NOT NULL,
+    entity_id integer NOT NULL,
+    action TEXT NOT NULL CHECK (action IN ('create', 'update', 'delete')),
+    actor_id integer,
+    created_at TIMESTAMP NOT NULL DEFAULT now(),
+    payload JSONB NOT NULL DEFAULT '{}'
+);
+
+CREATE OR REPLACE FUNCTION events_append_only() RETURNS trigger AS $$
+BEGIN
+    RAISE EXCEPTION 'events can only be appended to';
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER events_append_only BEFORE UPDATE OR DELETE ON events
+    FOR EACH ROW EXECUTE FUNCTION events_append_only();
@@ -0,0 +1,7 @@
+-- Login credentials point at their account by id, fixed when the account is registered,
+-- so renaming an account's email can not hand its role to whoever logs in with that email
+ALTER TABLE passwords
+    ADD COLUMN IF NOT EXISTS account_id integer REFERENCES accounts (id) ON DELETE SET NULL;
+
+UPDATE passwords p SET account_id = a.id FROM accounts a
+WHERE a.email = p.email AND p.account_id IS NULL;
@@ -0,0 +1,95 @@
+use crate::database::Answer;
+use crate::question::QuestionId;
+use std::collections::HashMap;
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio::sync::broadcast;
+use tokio_stream::wrappers::errors::BroadcastStreamRecvError;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::Stream;
+
+/// How many answers a slow stream subscriber may fall behind before it starts missing some
+const CHANNEL_CAPACITY: usize = 16;
+
+/// Answer streams struct
+///
+/// This struct holds one broadcast channel per question that somebody is streaming the answers of.
+/// Channels are created on the first subscriber and dropped along with the last one.
+/// ##Example:
+/// ```
+/// let mut answers = streams.subscribe(&QuestionId(1));
+/// streams.publish(&answer);
+/// let answer = answers.next().await;
+/// ```
+#[derive(Debug, Default)]
+pub struct AnswerStreams {
+    channels: Mutex<HashMap<i32, broadcast::Sender<Answer>>>,
+}
+
+impl AnswerStreams {
+    /// Function to subscribe to the new answers of a question
+    pub fn subscribe(self: &Arc<Self>, question_id: &QuestionId) -> Subscription {
+        let receiver = self
+            .channels
+            .lock()
+            .unwrap()
+            .entry(question_id.0)
+            .or_insert_with(|| broadcast::channel(CHANNEL_CAPACITY).0)
+            .subscribe();
+        Subscription {
+            streams: Arc::clone(self),
+            question_id: question_id.0,
+            answers: Some(BroadcastStream::new(receiver)),
+        }
+    }
+
+    /// Function to send a newly posted answer to everyone streaming its question
+    pub fn publish(&self, answer: &Answer) {
+        let mut channels = self.channels.lock().unwrap();
+        let question_id = answer.question_id.0;
+        if let Some(sender) = channels.get(&question_id) {
+            // Sending only fails when every subscriber has gone away
+            if sender.send(answer.clone()).is_err() {
+                channels.remove(&question_id);
+            }
+        }
+    }
+
+    /// Function to drop the channel of a question once nobody is streaming it any more
+    fn release(&self, question_id: i32) {
+        let mut channels = self.channels.lock().unwrap();
+        if channels
+            .get(&question_id)
+            .is_some_and(|sender| sender.receiver_count() == 0)
+        {
+            channels.remove(&question_id);
+        }
+    }
+}
+
+/// A subscription to the new answers of a question, a stream that gives up its channel when dropped
+pub struct Subscription {
+    streams: Arc<AnswerStreams>,
+    question_id: i32,
+    answers: Option<BroadcastStream<Answer>>,
+}
+
+impl Stream for Subscription {
+    type Item = Result<Answer, BroadcastStreamRecvError>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        match self.answers.as_mut() {
+            Some(answers) => Pin::new(answers).poll_next(cx),
+            None => Poll::Ready(None),
+        }
+    }
+}
+
+impl Drop for Subscription {
+    fn drop(&mut self) {
+        // The receiver goes first, so the count the release looks at no longer includes it
+        drop(self.answers.take());
+        self.streams.release(self.question_id);
+    }
+}
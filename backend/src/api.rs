@@ -1,9 +1,20 @@
+use axum::async_trait;
+use axum::extract::{ConnectInfo, FromRequest};
+use axum::http::header::WARNING;
+use axum::http::HeaderMap;
+use axum::response::sse::{Event, KeepAlive, Sse};
+use serde::de::DeserializeOwned;
+use std::collections::HashSet;
+use std::net::SocketAddr;
+use std::sync::atomic::Ordering;
+use tokio_stream::StreamExt;
 use tracing::{info, instrument};
 
+use crate::auth::{AdminClaims, AuthError, Claims};
 use crate::database::*;
 use crate::*;
 
-use self::bad_words_api::check_profanity;
+use self::bad_words_api::censor;
 
 #[derive(OpenApi)]
 #[openapi(
@@ -16,180 +27,762 @@ use self::bad_words_api::check_profanity;
         get_account,
         delete_account,
         put_account,
+        get_me,
+        get_account_by_id,
+        get_account_exists,
+        search_accounts,
+        get_search,
+        post_promote_account,
+        post_pin_question,
+        post_unpin_question,
+        post_mark_duplicate,
+        post_close_question,
         get_answers,
+        get_answer_count,
+        get_answer_stream,
         delete_answer,
         put_answer,
         post_answer,
+        post_question_answer,
+        post_qa,
+        post_answer_vote,
+        post_accept_answer,
+        post_answer_report,
+        get_question_with_answers,
+        get_unanswered_questions,
+        get_trending_questions,
+        get_feed,
+        get_question_history,
+        get_applied_migrations,
+        get_reports,
+        get_events,
+        get_maintenance,
+        post_maintenance,
+        post_rename_tag,
+        get_tags,
     ),
     components(
-        schemas(Question, ApiError, Account, Answer),
+        schemas(Question, NewQuestion, QuestionPreview, QuestionSummary, QuestionFields, QuestionCursorPage, Category, CloseReason, ApiError, Account, PublicAccount, Answer, AnswerBody, AccountExists, AnswerSort, AnswerCount, NewAnswer, NewQa, QaCreated, Vote, AnswerScore, NewReport, ReportKind, Report, EventAction, Event, AppliedMigration, Maintenance, TagRename, TagRenameResult, TagCount, TagSort, SearchResults, SimilarQuestionsWarning, QuestionWithAnswers, QuestionRevision),
     ),
     tags(
-        (name = "Question", description = "Questions API")
+        (name = "Question", description = "Questions API"),
+        (name = "Answer", description = "Answers API"),
+        (name = "Account", description = "Accounts API"),
+        (name = "Admin", description = "Operations API")
     )
 )]
 pub struct ApiDoc;
 
 /// A pagination struct
 ///
-/// This struct is used to paginate the questions in the API from a start to an end index
+/// This struct is used to paginate the questions in the API from a start to an end index,
+/// and to page through results with a limit and offset. The limit is bounded by the config.
 /// #Example:
 /// ```
 ///
 /// {
 ///   "start": "1",
-///   "end": "5"
+///   "end": "5",
+///   "limit": "20",
+///   "offset": "40",
+///   "category": "debugging",
+///   "preview": "true",
+///   "sort": "active",
+///   "after": "10",
+///   "tag": "rust,async",
+///   "not_tag": "beginner",
+///   "rich": "true",
+///   "fields": "summary"
 /// }
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Pagination {
     start: Option<QuestionId>,
     end: Option<QuestionId>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+    category: Option<Category>,
+    preview: Option<bool>,
+    sort: Option<QuestionSort>,
+    after: Option<QuestionId>,
+    /// Comma separated tags a question must all carry
+    tag: Option<String>,
+    /// Comma separated tags a question must carry none of
+    not_tag: Option<String>,
+    /// Whether each question keeps `has_accepted_answer` next to its `answer_count`, for rendering cards
+    rich: Option<bool>,
+    /// `summary` for a light page of ids, titles, tags and answer counts, without content
+    fields: Option<QuestionFields>,
 }
 
-/// API function to get all questions or a range of questions from the questions hashmap
-#[utoipa::path(get, path = "/questions", responses((
+/// Function to split a comma separated tag parameter into its tags, dropping empty ones
+fn split_tags(tags: Option<String>) -> Vec<String> {
+    tags.map(|tags| {
+        tags.split(',')
+            .map(|tag| tag.trim().to_string())
+            .filter(|tag| !tag.is_empty())
+            .collect()
+    })
+    .unwrap_or_default()
+}
+
+/// Function to build the response to `?fields=summary`, a page of question summaries with the same headers as a full page
+#[allow(clippy::too_many_arguments)]
+async fn question_summaries_response(
+    state: &AppState,
+    uri: &Uri,
+    include: &[String],
+    exclude: &[String],
+    limit: i64,
+    offset: i64,
+    clamped: bool,
+    category: Option<Category>,
+    sort: QuestionSort,
+) -> Response<String> {
+    let summaries = match state
+        .question_summaries_page(include, exclude, limit, offset, category, sort)
+        .await
+    {
+        Ok(summaries) => summaries,
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, error);
+        }
+    };
+    let total = match state.count_questions(include, exclude, category).await {
+        Ok(total) => total,
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, error);
+        }
+    };
+    let mut response = page_response(
+        serde_json::to_string_pretty(&summaries).unwrap(),
+        limit,
+        clamped,
+    );
+    let response_headers = response.headers_mut();
+    response_headers.insert("X-Total-Count", HeaderValue::from(total));
+    if let Ok(links) = HeaderValue::from_str(&pagination_links(uri, limit, offset, total)) {
+        response_headers.insert("Link", links);
+    }
+    response
+}
+
+/// Function to drop the accepted status from questions unless `?rich=true` asked for it,
+/// so the plain list keeps the shape simple clients expect
+fn plain_unless_rich(questions: Vec<Question>, rich: bool) -> Vec<Question> {
+    if rich {
+        return questions;
+    }
+    questions
+        .into_iter()
+        .map(|question| Question {
+            has_accepted_answer: None,
+            ..question
+        })
+        .collect()
+}
+
+/// Function to build the RFC 5988 `Link` header of a page, with the first, last, previous and next pages
+///
+/// Each link keeps the request's own query parameters apart from `limit` and `offset`.
+/// `prev` is left out on the first page and `next` on the last
+fn pagination_links(uri: &Uri, limit: i64, offset: i64, total: i64) -> String {
+    let kept: String = uri
+        .query()
+        .unwrap_or_default()
+        .split('&')
+        .filter(|pair| {
+            !pair.is_empty() && !pair.starts_with("limit=") && !pair.starts_with("offset=")
+        })
+        .map(|pair| format!("{}&", pair))
+        .collect();
+    let link = |offset: i64, rel: &str| {
+        format!(
+            "<{}?{}limit={}&offset={}>; rel=\"{}\"",
+            uri.path(),
+            kept,
+            limit,
+            offset,
+            rel
+        )
+    };
+    let last = if total > 0 {
+        (total - 1) / limit * limit
+    } else {
+        0
+    };
+    let mut links = vec![link(0, "first")];
+    if offset > 0 {
+        links.push(link((offset - limit).max(0), "prev"));
+    }
+    if offset + limit < total {
+        links.push(link(offset + limit, "next"));
+    }
+    links.push(link(last, "last"));
+    links.join(", ")
+}
+
+/// A cursor page struct, a page of questions and the cursor to get the page after it with
+///
+/// `next_cursor` is left out once there are no more questions
+/// ##Example:
+/// ```
+/// {
+///   "questions": [{ "id": 11, "title": "What is rust?", "content": "...", "category": "general" }],
+///   "next_cursor": 11
+/// }
+/// ```
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QuestionCursorPage {
+    pub questions: Vec<Question>,
+    #[schema(example = 11, value_type = Option<i32>)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<QuestionId>,
+}
+
+/// Function to serialize a list of questions, cutting their content short when a preview was asked for
+fn questions_body(questions: Vec<Question>, preview: bool) -> String {
+    if preview {
+        let previews: Vec<QuestionPreview> =
+            questions.into_iter().map(QuestionPreview::from).collect();
+        serde_json::to_string_pretty(&previews).unwrap()
+    } else {
+        serde_json::to_string_pretty(&questions).unwrap()
+    }
+}
+
+/// Function to build an error response with the standard error envelope
+///
+/// Never panics, whatever the message, so it is safe to use on any error path
+/// #Example:
+/// ```
+/// { "status": 404, "error": "Question not found" }
+/// ```
+pub fn json_error(status: StatusCode, message: impl std::fmt::Display) -> Response<String> {
+    let body = serde_json::json!({
+        "status": status.as_u16(),
+        "error": message.to_string(),
+    });
+    let mut response = Response::new(body.to_string());
+    *response.status_mut() = status;
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+/// Function to build a response with the given status and body, without a builder that could panic
+pub fn text_response(status: StatusCode, body: impl Into<String>) -> Response<String> {
+    let mut response = Response::new(body.into());
+    *response.status_mut() = status;
+    response
+}
+
+/// Function to build a response with the given status and an already serialized JSON body
+pub fn json_response(status: StatusCode, body: String) -> Response<String> {
+    let mut response = text_response(status, body);
+    response
+        .headers_mut()
+        .insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    response
+}
+
+/// A JSON body extractor whose errors are JSON too
+///
+/// Axum's own `Json` answers a body it can not read with a plain text 422. This answers with the
+/// usual error body and a 400 instead, naming what was wrong, such as an unknown field from a typo
+#[derive(Debug)]
+pub struct StrictJson<T>(pub T);
+
+#[async_trait]
+impl<T, S> FromRequest<S> for StrictJson<T>
+where
+    T: DeserializeOwned,
+    S: Send + Sync,
+{
+    type Rejection = Response<String>;
+
+    async fn from_request(request: Request, state: &S) -> Result<Self, Self::Rejection> {
+        match Json::<T>::from_request(request, state).await {
+            Ok(Json(value)) => Ok(StrictJson(value)),
+            Err(rejection) => {
+                // A wrong content type or a body too large keep their own status
+                let status = match rejection.status() {
+                    StatusCode::UNPROCESSABLE_ENTITY => StatusCode::BAD_REQUEST,
+                    status => status,
+                };
+                Err(json_error(status, rejection.body_text()))
+            }
+        }
+    }
+}
+
+/// Function to build a paged OK response, adding a Warning header if the requested limit was clamped
+fn page_response(body: String, limit: i64, clamped: bool) -> Response<String> {
+    let mut response = text_response(StatusCode::OK, body);
+    if clamped {
+        if let Ok(warning) = HeaderValue::from_str(&format!("299 - \"limit clamped to {}\"", limit))
+        {
+            response.headers_mut().insert(WARNING, warning);
+        }
+    }
+    response
+}
+
+/// API function to get a page of questions or a range of questions from the questions database
+///
+/// With `?after=<id>` the page is the questions after that id instead, wrapped with the cursor for the next page.
+/// A page can be narrowed by tags, `?tag=rust&not_tag=beginner` gives the questions tagged rust but not beginner.
+/// Pages come with an `X-Total-Count` header and a `Link` header pointing at the pages around them.
+/// With `?rich=true` each question of a page also says whether it has an accepted answer.
+/// Pages are cached for QUESTION_CACHE_MS, any write clears the cache.
+/// `?fields=summary` gives a light page of ids, titles, tags and answer counts instead, content left out
+#[utoipa::path(get, path = "/questions", tag = "Question", responses((
     status = 200,
-    description = "Returns all questions or a range of questions",
+    description = "Returns a page of questions, a range of questions or a cursor page",
     body = None
 ),
-(status = 204, description = "Questions db is empty", body = ApiError)))]
+(status = 204, description = "Questions db is empty", body = ApiError, example = json!({"status": 204, "error": "Questions db is empty"}))))]
 #[instrument]
 pub async fn get_questions(
     State(state): State<AppState>,
-    Query(Pagination { start, end }): Query<Pagination>,
+    headers: HeaderMap,
+    uri: Uri,
+    Query(Pagination {
+        start,
+        end,
+        limit,
+        offset,
+        category,
+        preview,
+        sort,
+        after,
+        tag,
+        not_tag,
+        rich,
+        fields,
+    }): Query<Pagination>,
 ) -> impl IntoResponse {
-    let questions = state.get_all_questions().await.unwrap();
+    let preview = preview.unwrap_or(false);
+    let rich = rich.unwrap_or(false);
+    let (limit, clamped) = state.2.page_size(limit);
+    let offset = offset.unwrap_or(0).max(0);
+    if let Some(after) = after {
+        info!("Getting the questions after {}", after.0);
+        let questions = match state.questions_after_cursor(&after, limit, category).await {
+            Ok(questions) => plain_unless_rich(questions, rich),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return json_error(StatusCode::INTERNAL_SERVER_ERROR, error);
+            }
+        };
+        // A short page is the last one
+        let next_cursor = if questions.len() as i64 == limit {
+            questions.last().map(|question| question.id.clone())
+        } else {
+            None
+        };
+        let page = QuestionCursorPage {
+            questions,
+            next_cursor,
+        };
+        return page_response(serde_json::to_string_pretty(&page).unwrap(), limit, clamped);
+    }
     if start.is_none() && end.is_none() {
-        info!("Getting all questions");
-        Response::builder()
-            .status(StatusCode::OK)
-            .body(serde_json::to_string_pretty(&questions.clone()).unwrap())
-            .unwrap()
+        info!("Getting a page of questions");
+        let include = split_tags(tag);
+        let exclude = split_tags(not_tag);
+        let sort = sort.unwrap_or_default();
+        if fields.unwrap_or_default() == QuestionFields::Summary {
+            return question_summaries_response(
+                &state, &uri, &include, &exclude, limit, offset, clamped, category, sort,
+            )
+            .await;
+        }
+        let cache_key = format!("{:?}", (limit, offset, category, sort, &include, &exclude));
+        let (questions, total) = match state.7.get(&cache_key) {
+            Some(page) => page,
+            None => {
+                let generation = state.7.generation();
+                let questions = if include.is_empty() && exclude.is_empty() {
+                    state
+                        .get_questions_page(limit, offset, category, sort)
+                        .await
+                } else {
+                    state
+                        .filter_questions_by_tags(&include, &exclude, limit, offset, category, sort)
+                        .await
+                };
+                let questions = match questions {
+                    Ok(questions) => questions,
+                    Err(error) => {
+                        tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                        return json_error(StatusCode::INTERNAL_SERVER_ERROR, error);
+                    }
+                };
+                let total = match state.count_questions(&include, &exclude, category).await {
+                    Ok(total) => total,
+                    Err(error) => {
+                        tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                        return json_error(StatusCode::INTERNAL_SERVER_ERROR, error);
+                    }
+                };
+                state
+                    .7
+                    .insert(cache_key, (questions.clone(), total), generation);
+                (questions, total)
+            }
+        };
+        let questions = plain_unless_rich(questions, rich);
+        let mut response = page_response(questions_body(questions, preview), limit, clamped);
+        let response_headers = response.headers_mut();
+        response_headers.insert("X-Total-Count", HeaderValue::from(total));
+        if let Ok(links) = HeaderValue::from_str(&pagination_links(&uri, limit, offset, total)) {
+            response_headers.insert("Link", links);
+        }
+        json_api::negotiate(&headers, response)
     } else {
+        let questions = match state.get_all_questions().await {
+            Ok(questions) => questions,
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return json_error(StatusCode::INTERNAL_SERVER_ERROR, error);
+            }
+        };
         let mut result = Vec::new();
         let start_index = match start {
             Some(s) => s.0,
             None => {
-                return Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .body(ApiError::MissingParameters.to_string())
-                    .unwrap();
+                return json_error(
+                    StatusCode::BAD_REQUEST,
+                    ApiError::MissingParameters.to_string(),
+                );
             }
         };
         let end_index = match end {
             Some(s) => s.0,
             None => {
-                return Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .body(ApiError::MissingParameters.to_string())
-                    .unwrap();
+                return json_error(
+                    StatusCode::BAD_REQUEST,
+                    ApiError::MissingParameters.to_string(),
+                );
             }
         };
         for question in questions {
-            if question.id.0 >= start_index && question.id.0 <= end_index {
+            if question.id.0 >= start_index
+                && question.id.0 <= end_index
+                && category.is_none_or(|category| question.category == category)
+            {
                 result.push(question);
             }
         }
-        Response::builder()
-            .status(StatusCode::OK)
-            .body(serde_json::to_string_pretty(&result).unwrap())
-            .unwrap()
+        let result: Vec<Question> = result
+            .into_iter()
+            .skip(offset as usize)
+            .take(limit as usize)
+            .collect();
+        json_api::negotiate(
+            &headers,
+            page_response(questions_body(result, preview), limit, clamped),
+        )
+    }
+}
+
+/// API function to get a page of the questions that have no answers yet, newest first
+#[utoipa::path(get, path = "/questions/unanswered", tag = "Question", responses((
+    status = 200,
+    description = "Returns a page of unanswered questions",
+    body = [Question]
+),
+(status = 500, description = "Failed to get unanswered questions", body = ApiError, example = json!({"status": 500, "error": "Failed to get unanswered questions"}))))]
+#[instrument]
+pub async fn get_unanswered_questions(
+    State(state): State<AppState>,
+    Query(Pagination { limit, offset, .. }): Query<Pagination>,
+) -> impl IntoResponse {
+    let (limit, clamped) = state.2.page_size(limit);
+    let offset = offset.unwrap_or(0).max(0);
+    match state.unanswered_questions(limit, offset).await {
+        Ok(questions) => page_response(
+            serde_json::to_string_pretty(&questions).unwrap(),
+            limit,
+            clamped,
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+        }
+    }
+}
+
+/// Longest window, in hours, trending questions can be counted over
+pub const MAX_TRENDING_HOURS: i32 = 24 * 30;
+
+/// A trending param struct
+///
+/// How many hours back answers count towards a question trending, 24 when not given, and how many questions to return
+/// ##Example:
+/// ```
+/// {
+///  "hours": "24",
+///  "limit": "10"
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TrendingParam {
+    pub hours: Option<i32>,
+    pub limit: Option<i64>,
+}
+
+/// API function to get the questions gaining attention, ranked by how many answers they got in the last hours
+#[utoipa::path(get, path = "/questions/trending", tag = "Question", responses((
+    status = 200,
+    description = "Returns the trending questions, most recent answers first",
+    body = [Question]
+),
+(status = 500, description = "Failed to get trending questions", body = ApiError, example = json!({"status": 500, "error": "Failed to get trending questions"}))))]
+#[instrument]
+pub async fn get_trending_questions(
+    State(state): State<AppState>,
+    Query(TrendingParam { hours, limit }): Query<TrendingParam>,
+) -> impl IntoResponse {
+    let hours = hours.unwrap_or(24).clamp(1, MAX_TRENDING_HOURS);
+    let (limit, clamped) = state.2.page_size(limit);
+    match state.trending_questions(hours, limit).await {
+        Ok(questions) => page_response(
+            serde_json::to_string_pretty(&questions).unwrap(),
+            limit,
+            clamped,
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+        }
+    }
+}
+
+/// How many questions the RSS feed lists, the most recently asked ones
+pub const FEED_LENGTH: i64 = 50;
+
+/// API function to get an RSS feed of the most recently asked questions
+///
+/// Each entry links to the question's page on the frontend at SITE_URL
+#[utoipa::path(get, path = "/feed.xml", tag = "Question", responses((
+    status = 200,
+    description = "Returns an RSS 2.0 feed of the latest questions",
+    body = String,
+    content_type = "application/rss+xml"
+),
+(status = 500, description = "Failed to get the feed", body = ApiError, example = json!({"status": 500, "error": "Database error: connection refused"}))))]
+#[instrument]
+pub async fn get_feed(State(state): State<AppState>) -> impl IntoResponse {
+    let entries = match state.feed_entries(FEED_LENGTH).await {
+        Ok(entries) => entries,
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, error.to_string());
+        }
+    };
+    let site_url = state.2.site_url.trim_end_matches('/');
+    let items = entries
+        .into_iter()
+        .map(|entry| {
+            let link = format!("{}/question/{}", site_url, entry.id.0);
+            rss::ItemBuilder::default()
+                .title(Some(entry.title))
+                .link(Some(link.clone()))
+                .guid(Some(rss::Guid {
+                    value: link,
+                    permalink: true,
+                }))
+                .pub_date(Some(entry.created_on.and_utc().to_rfc2822()))
+                .build()
+        })
+        .collect::<Vec<rss::Item>>();
+    let channel = rss::ChannelBuilder::default()
+        .title("Questions")
+        .link(site_url)
+        .description("The most recently asked questions")
+        .items(items)
+        .build();
+    let mut response = text_response(StatusCode::OK, channel.to_string());
+    response.headers_mut().insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/rss+xml; charset=utf-8"),
+    );
+    response
+}
+
+/// API function to get the edit history of a question, newest revision first
+#[utoipa::path(get, path = "/questions/:id/history", tag = "Question", responses((
+    status = 200,
+    description = "Returns the earlier revisions of the question",
+    body = [QuestionRevision]
+),
+(status = 404, description = "Question not found", body = ApiError, example = json!({"status": 404, "error": "Question not found"}))))]
+#[instrument]
+pub async fn get_question_history(
+    State(state): State<AppState>,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    let question_id = QuestionId(id);
+    match state.get_question(&question_id).await {
+        Ok(Some(_)) => (),
+        Ok(None) => {
+            return json_error(
+                StatusCode::NOT_FOUND,
+                ApiError::QuestionNotFound.to_string(),
+            );
+        }
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, error.to_string());
+        }
+    }
+    match state.get_question_history(&question_id).await {
+        Ok(revisions) => json_response(
+            StatusCode::OK,
+            serde_json::to_string_pretty(&revisions).unwrap(),
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+        }
     }
 }
 
 /// API function to handle request to delete a question from the questions "Database"
 #[instrument]
-#[utoipa::path(delete, path = "/questions/:id", responses((
+#[utoipa::path(delete, path = "/questions/:id", tag = "Question", responses((
     status = 200,
     description = "Question deleted"
 ),
-(status = 404, description = "Question not found", body = ApiError)))]
+(status = 404, description = "Question not found", body = ApiError, example = json!({"status": 404, "error": "Question not found"}))))]
 pub async fn delete_question(
     State(state): State<AppState>,
+    claims: Option<Claims>,
     Query(IdParam { id }): Query<IdParam>,
 ) -> impl IntoResponse {
-    let question_id = QuestionId(id.unwrap());
-    if state.get_question(&question_id).await.is_err() {
-        return Response::builder()
-            .status(StatusCode::NOT_FOUND)
-            .body(ApiError::QuestionNotFound.to_string())
-            .unwrap();
+    let question_id = match id {
+        Some(id) => QuestionId(id),
+        None => return json_error(StatusCode::BAD_REQUEST, ApiError::MissingParameters),
+    };
+    match state.get_question(&question_id).await {
+        Ok(Some(_)) => (),
+        Ok(None) => {
+            return json_error(
+                StatusCode::NOT_FOUND,
+                ApiError::QuestionNotFound.to_string(),
+            );
+        }
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, error.to_string());
+        }
     }
-    match state.delete_question(&question_id).await {
+    let actor_id = claims.and_then(|claims| claims.account_id);
+    match state.delete_question(&question_id, actor_id).await {
+        Ok(0) => {
+            return json_error(
+                StatusCode::NOT_FOUND,
+                ApiError::QuestionNotFound.to_string(),
+            );
+        }
         Ok(_) => (),
         Err(_) => {
-            return Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body("Failed to delete question".into())
-                .unwrap();
-        }
-    }
-    Response::builder()
-        .status(StatusCode::OK)
-        .body("Question deleted".to_string())
-        .unwrap_or_else(|_| {
-            Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body("Failed to delete question".into())
-                .unwrap()
-        })
+            return json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to delete question",
+            );
+        }
+    }
+    text_response(StatusCode::OK, "Question deleted")
 }
 
 /// API function to handle request to update a question in the questions "Database"
 #[instrument]
-#[utoipa::path(put, path = "/questions/:id", responses((
+#[utoipa::path(put, path = "/questions/:id", tag = "Question", responses((
     status = 200,
     description = "Question updated",
     body = UpdateQuestion
 ),
-(status = 404, description = "Question not found", body = ApiError)))]
+(status = 400, description = "Too many or too long tags, or content too profane", body = ApiError, example = json!({"status": 400, "error": "Invalid tags: at most 10 tags are allowed"})),
+(status = 404, description = "Question not found", body = ApiError, example = json!({"status": 404, "error": "Question not found"}))))]
 pub async fn put_question(
     State(state): State<AppState>,
+    claims: Option<Claims>,
     Query(IdParam { id }): Query<IdParam>,
-    Json(question): Json<question::UpdateQuestion>,
+    StrictJson(question): StrictJson<question::UpdateQuestion>,
 ) -> impl IntoResponse {
     let question_id = match id {
         Some(id) => QuestionId(id),
         None => match question.id {
             Some(id) => id,
             None => {
-                return Response::builder()
-                    .status(StatusCode::BAD_REQUEST)
-                    .body(ApiError::MissingParameters.to_string())
-                    .unwrap();
+                return json_error(
+                    StatusCode::BAD_REQUEST,
+                    ApiError::MissingParameters.to_string(),
+                );
             }
         },
     };
-    if state.get_question(&question_id).await.is_err() {
-        return Response::builder()
-            .status(StatusCode::NOT_FOUND)
-            .body(ApiError::QuestionNotFound.to_string())
-            .unwrap();
+    if let Err(error) = question::validate_tags(&question.tags) {
+        return json_error(StatusCode::BAD_REQUEST, error.to_string());
     }
+    let existing = match state.get_question(&question_id).await {
+        Ok(Some(existing)) => existing,
+        Ok(None) => {
+            return json_error(
+                StatusCode::NOT_FOUND,
+                ApiError::QuestionNotFound.to_string(),
+            );
+        }
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, error.to_string());
+        }
+    };
+    let title = match censor(&state.2, &state.5, "title", question.title).await {
+        Ok(title) => title,
+        Err(error) => return json_error(error.status(), error),
+    };
+    let content = match censor(&state.2, &state.5, "content", question.content).await {
+        Ok(content) => content,
+        Err(error) => return json_error(error.status(), error),
+    };
     let updated_question = Question {
         id: question_id.clone(),
-        title: check_profanity(question.title).await.unwrap(),
-        content: check_profanity(question.content).await.unwrap(),
+        title,
+        content,
         tags: question.tags,
+        category: question.category.unwrap_or(existing.category),
+        pinned: false,
+        answer_count: None,
+        has_accepted_answer: None,
+        duplicate_of: None,
+        close_reason: None,
     };
-    match state.update_question(&question_id, updated_question).await {
+    let actor_id = claims.and_then(|claims| claims.account_id);
+    match state
+        .update_question(&question_id, updated_question, actor_id)
+        .await
+    {
+        Ok(0) => {
+            return json_error(
+                StatusCode::NOT_FOUND,
+                ApiError::QuestionNotFound.to_string(),
+            );
+        }
         Ok(_) => (),
         Err(_) => {
-            return Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body("Failed to update question".into())
-                .unwrap();
-        }
-    }
-    Response::builder()
-        .status(StatusCode::OK)
-        .body("Question updated".to_string())
-        .unwrap_or_else(|_| {
-            Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body("Failed to update question".into())
-                .unwrap()
-        })
+            return json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to update question",
+            );
+        }
+    }
+    text_response(StatusCode::OK, "Question updated")
 }
 
 /// A parameter struct for the question id
@@ -205,6 +798,58 @@ pub struct IdParam {
     pub id: Option<i32>,
 }
 
+/// Most question ids a single bulk answer fetch may ask for
+pub const MAX_BULK_QUESTION_IDS: usize = 50;
+
+/// A parameter struct for fetching the answers of several questions at once
+///
+/// This struct is used to get a comma separated list of question ids from the query parameters
+/// ##Example:
+/// ```
+/// {
+///  "question_ids": "1,2,3"
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct QuestionIdsParam {
+    pub question_ids: Option<String>,
+}
+
+/// An answer page struct
+///
+/// This struct is used to page through the answers to a question and pick their order,
+/// ANSWER_SORT_DEFAULT is used when no sort is given
+/// ##Example:
+/// ```
+/// {
+///  "limit": "20",
+///  "offset": "40",
+///  "sort": "newest"
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct AnswerPagination {
+    limit: Option<i64>,
+    offset: Option<i64>,
+    sort: Option<AnswerSort>,
+}
+
+/// Function to parse a comma separated list of question ids, enforcing `MAX_BULK_QUESTION_IDS`
+fn parse_question_ids(question_ids: &str) -> Result<Vec<i32>, ApiError> {
+    let ids = question_ids
+        .split(',')
+        .map(|id| id.trim().parse::<i32>())
+        .collect::<Result<Vec<i32>, _>>()
+        .map_err(|_| ApiError::InvalidQuestionIds("ids must be integers".to_string()))?;
+    if ids.len() > MAX_BULK_QUESTION_IDS {
+        return Err(ApiError::InvalidQuestionIds(format!(
+            "at most {} ids are allowed",
+            MAX_BULK_QUESTION_IDS
+        )));
+    }
+    Ok(ids)
+}
+
 /// A parameter struct for the user email
 ///
 /// This struct is used to get the user email from the query parameters
@@ -219,39 +864,149 @@ pub struct UserAccountInfo {
     pub password: Option<String>,
 }
 
+/// A cascade parameter struct
+///
+/// This struct is used to say whether deleting an account deletes its questions and answers too,
+/// ACCOUNT_DELETE_CASCADE is used when it is left out
+/// ##Example:
+/// ```
+/// {
+///  "cascade": "true"
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CascadeParam {
+    pub cascade: Option<bool>,
+}
+
+/// Most similar questions listed in a duplicate warning
+const MAX_SIMILAR_QUESTIONS: i64 = 5;
+
+/// A parameter struct for skipping the duplicate title check when posting a question
+///
+/// ##Example:
+/// ```
+/// {
+///  "force": "true"
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct ForceParam {
+    pub force: Option<bool>,
+}
+
+/// A warning struct, returned instead of adding a question whose title is close to existing ones
+///
+/// ##Example:
+/// ```
+/// {
+///  "warning": "Similar questions already exist, post again with ?force=true to add it anyway",
+///  "similar": [{ "id": 1, "title": "What is rust?", "content": "...", "category": "general", "answer_count": 3 }]
+/// }
+/// ```
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SimilarQuestionsWarning {
+    #[schema(
+        example = "Similar questions already exist, post again with ?force=true to add it anyway"
+    )]
+    pub warning: String,
+    pub similar: Vec<Question>,
+}
+
 /// Function to post a question to the "database"
 ///
 /// Currently only modifies the state of the application by adding a question to the questions hashmap, but will add write to file soon
 #[instrument]
-#[utoipa::path(post, path = "/questions", responses((
+#[utoipa::path(post, path = "/questions", tag = "Question", request_body = NewQuestion, responses((
     status = 200,
     description = "Question added",
     body = Question
 ),
-(status = 500, description = "Failed to add question", body = ApiError)))]
+(status = 200, description = "Not added, similar questions already exist", body = SimilarQuestionsWarning),
+(status = 400, description = "Too many or too long tags, or content too profane", body = ApiError, example = json!({"status": 400, "error": "Invalid tags: at most 10 tags are allowed"})),
+(status = 401, description = "Login required when anonymous questions are not allowed", body = ApiError, example = json!({"status": 401, "error": "Login required"})),
+(status = 500, description = "Failed to add question", body = ApiError, example = json!({"status": 500, "error": "Failed to add question"}))))]
 pub async fn post_question(
     State(state): State<AppState>,
-    Json(question): Json<Question>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    claims: Option<Claims>,
+    Query(ForceParam { force }): Query<ForceParam>,
+    StrictJson(question): StrictJson<NewQuestion>,
 ) -> impl IntoResponse {
+    if claims.is_none() {
+        if !state.2.allow_anonymous_questions {
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                ApiError::LoginRequired.to_string(),
+            );
+        }
+        // Anonymous posts get a much stricter limit of their own, nothing else holds spam back
+        if let Err(retry_after) = state.6.check(addr.ip()) {
+            let mut response = json_error(
+                StatusCode::TOO_MANY_REQUESTS,
+                ApiError::AnonymousRateLimited,
+            );
+            response.headers_mut().insert(
+                "Retry-After",
+                HeaderValue::from(retry_after.as_secs().max(1)),
+            );
+            return response;
+        }
+    }
+    if let Err(error) = question::validate_tags(&question.tags) {
+        return json_error(StatusCode::BAD_REQUEST, error.to_string());
+    }
+    // Point the asker at likely duplicates first, unless they already saw them and insist
+    if !force.unwrap_or(false) {
+        match state
+            .similar_titles(
+                &question.title,
+                state.2.similar_title_threshold,
+                MAX_SIMILAR_QUESTIONS,
+            )
+            .await
+        {
+            Ok(similar) if !similar.is_empty() => {
+                let warning = SimilarQuestionsWarning {
+                    warning: "Similar questions already exist, post again with ?force=true to add it anyway"
+                        .to_string(),
+                    similar,
+                };
+                return json_response(
+                    StatusCode::OK,
+                    serde_json::to_string_pretty(&warning).unwrap(),
+                );
+            }
+            Ok(_) => (),
+            Err(error) => tracing::event!(tracing::Level::ERROR, "{:?}", error),
+        }
+    }
+    let title = match censor(&state.2, &state.5, "title", question.title.clone()).await {
+        Ok(title) => title,
+        Err(error) => return json_error(error.status(), error),
+    };
+    let content = match censor(&state.2, &state.5, "content", question.content.clone()).await {
+        Ok(content) => content,
+        Err(error) => return json_error(error.status(), error),
+    };
     let question = Question {
         id: QuestionId(0),
-        title: check_profanity(question.title.clone()).await.unwrap(),
-        content: check_profanity(question.content.clone()).await.unwrap(),
+        title,
+        content,
         tags: question.tags.clone(),
+        category: question.category,
+        pinned: false,
+        answer_count: None,
+        has_accepted_answer: None,
+        duplicate_of: None,
+        close_reason: None,
     };
-    match state.add_question(question).await {
-        Ok(_) => {
-            return Response::builder()
-                .status(StatusCode::OK)
-                .body("Question added".to_string())
-                .unwrap()
-        }
+    let author_id = claims.and_then(|claims| claims.account_id);
+    match state.add_question(question, author_id).await {
+        Ok(_) => return text_response(StatusCode::OK, "Question added"),
         Err(error) => {
             tracing::event!(tracing::Level::ERROR, "{:?}", error);
-            return Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(error.to_string())
-                .unwrap();
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, error.to_string());
         }
     }
 }
@@ -259,39 +1014,94 @@ pub async fn post_question(
 /// Function to create an account in the "database"
 ///
 #[instrument]
-#[utoipa::path(post, path = "/account", responses((
+#[utoipa::path(post, path = "/account", tag = "Account", responses((
     status = 200,
     description = "Account added",
     body = None
 ),
-(status = 500, description = "Failed to add account", body = ApiError)))]
+(status = 400, description = "Invalid email or weak password", body = ApiError, example = json!({"status": 400, "error": "Invalid account: password must contain at least one letter and one digit"})),
+(status = 500, description = "Failed to add account", body = ApiError, example = json!({"status": 500, "error": "Failed to add account"}))))]
 pub async fn post_account(
     State(state): State<AppState>,
     Json(account): Json<Account>,
 ) -> impl IntoResponse {
+    if let Err(error) = account.validate() {
+        return json_error(StatusCode::BAD_REQUEST, error.to_string());
+    }
     match state.add_account(account).await {
-        Ok(_) => Response::builder()
-            .status(StatusCode::OK)
-            .body("Account added".to_string())
-            .unwrap(),
+        Ok(_) => text_response(StatusCode::OK, "Account added"),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
+        }
+    }
+}
+
+/// An account exists struct, whether an email already has an account
+///
+/// ##Example:
+/// ```
+/// {
+///  "exists": true
+/// }
+/// ```
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AccountExists {
+    pub exists: bool,
+}
+
+/// Function to check if an email already has an account, so a registration form can warn before submitting
+///
+/// Each IP may only check ACCOUNT_EXISTS_CHECKS_PER_MINUTE emails a minute, to keep it from being used to list accounts
+#[instrument]
+#[utoipa::path(get, path = "/accounts/exists", tag = "Account", responses((
+    status = 200,
+    description = "Returns whether the email has an account",
+    body = AccountExists
+),
+(status = 400, description = "Missing email", body = ApiError, example = json!({"status": 400, "error": "Missing parameter"})),
+(status = 429, description = "Too many checks from this IP", body = ApiError, example = json!({"status": 429, "error": "Too many requests, try again later"})),
+(status = 500, description = "Failed to check the email", body = ApiError, example = json!({"status": 500, "error": "Database error: connection refused"}))))]
+pub async fn get_account_exists(
+    State(state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Query(UserAccountInfo { email, .. }): Query<UserAccountInfo>,
+) -> impl IntoResponse {
+    let email = match email {
+        Some(email) => email,
+        None => return json_error(StatusCode::BAD_REQUEST, ApiError::MissingParameters),
+    };
+    if let Err(retry_after) = state.8.check(addr.ip()) {
+        let mut response = json_error(StatusCode::TOO_MANY_REQUESTS, ApiError::RateLimited);
+        response.headers_mut().insert(
+            "Retry-After",
+            HeaderValue::from(retry_after.as_secs().max(1)),
+        );
+        return response;
+    }
+    match state.account_exists(email.trim()).await {
+        Ok(exists) => json_response(
+            StatusCode::OK,
+            serde_json::to_string_pretty(&AccountExists { exists }).unwrap(),
+        ),
         Err(error) => {
             tracing::event!(tracing::Level::ERROR, "{:?}", error);
-            Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(error.to_string())
-                .unwrap()
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiError::DatabaseError(error.to_string()).to_string(),
+            )
         }
     }
 }
 
 /// Function to get an account from the "database"
 #[instrument]
-#[utoipa::path(get, path = "/account", responses((
+#[utoipa::path(get, path = "/account", tag = "Account", responses((
     status = 200,
     description = "Returns all accounts",
     body = None
 ),
-(status = 404, description = "Account not found", body = ApiError)))]
+(status = 404, description = "Account not found", body = ApiError, example = json!({"status": 404, "error": "Account not found"}))))]
 pub async fn get_account(
     State(state): State<AppState>,
     Query(UserAccountInfo { email, password }): Query<UserAccountInfo>,
@@ -299,214 +1109,1605 @@ pub async fn get_account(
     let email = match email {
         Some(email) => email,
         None => {
-            return Response::builder()
-                .status(StatusCode::BAD_REQUEST)
-                .body(ApiError::MissingParameters.to_string())
-                .unwrap();
+            return json_error(
+                StatusCode::BAD_REQUEST,
+                ApiError::MissingParameters.to_string(),
+            );
         }
     };
     match state.get_account(&email).await {
-        Ok(account) => Response::builder()
-            .status(StatusCode::OK)
-            .body(serde_json::to_string_pretty(&account).unwrap())
-            .unwrap(),
+        Ok(account) => json_response(
+            StatusCode::OK,
+            serde_json::to_string_pretty(&account).unwrap(),
+        ),
         Err(error) => {
             tracing::event!(tracing::Level::ERROR, "{:?}", error);
-            return Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body(ApiError::AccountNotFound.to_string())
-                .unwrap();
+            return json_error(StatusCode::NOT_FOUND, ApiError::AccountNotFound.to_string());
         }
     }
 }
 
 /// Function to delete an account from the "database"
+///
+/// With `?cascade=true` the account's questions and answers are deleted with it,
+/// otherwise they are kept and credited to the deleted user placeholder account.
+/// Callers may only delete their own account, unless they are an admin
 #[instrument]
-#[utoipa::path(delete, path = "/account", responses((
+#[utoipa::path(delete, path = "/account", tag = "Account", responses((
     status = 200,
     description = "Account deleted",
     body = None
 ),
-(status = 404, description = "Account not found", body = ApiError)))]
+(status = 400, description = "Tried to delete the deleted user placeholder", body = ApiError, example = json!({"status": 400, "error": "Invalid account: the deleted user placeholder can not be deleted"})),
+(status = 401, description = "Login required", body = AuthError, example = json!({"status": 401, "error": "Invalid token"})),
+(status = 403, description = "Caller does not own the account", body = ApiError, example = json!({"status": 403, "error": "Only the account itself or an admin can change it"})),
+(status = 404, description = "Account not found", body = ApiError, example = json!({"status": 404, "error": "Account not found"})),
+(status = 500, description = "Failed to delete the account", body = ApiError, example = json!({"status": 500, "error": "Database error: connection refused"}))))]
 pub async fn delete_account(
     State(state): State<AppState>,
+    claims: Claims,
     Query(UserAccountInfo { email, password }): Query<UserAccountInfo>,
+    Query(CascadeParam { cascade }): Query<CascadeParam>,
 ) -> impl IntoResponse {
     let email = match email {
         Some(email) => email,
         None => {
-            return Response::builder()
-                .status(StatusCode::BAD_REQUEST)
-                .body(ApiError::MissingParameters.to_string())
-                .unwrap();
+            return json_error(
+                StatusCode::BAD_REQUEST,
+                ApiError::MissingParameters.to_string(),
+            );
         }
     };
-    match state.delete_account(&email).await {
-        Ok(_) => Response::builder()
-            .status(StatusCode::OK)
-            .body("Account deleted".to_string())
-            .unwrap(),
+    if email == DELETED_USER_EMAIL {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            ApiError::InvalidAccount("the deleted user placeholder can not be deleted".to_string()),
+        );
+    }
+    let account_id = match owned_account_id(&state, &claims, &email).await {
+        Ok(account_id) => account_id,
+        Err(error) => return json_error(error.status(), error),
+    };
+    let cascade = cascade.unwrap_or(state.2.account_delete_cascade);
+    match state
+        .delete_account(&account_id, cascade, claims.account_id)
+        .await
+    {
+        Ok(0) => json_error(StatusCode::NOT_FOUND, ApiError::AccountNotFound.to_string()),
+        Ok(_) => text_response(StatusCode::OK, "Account deleted"),
         Err(error) => {
             tracing::event!(tracing::Level::ERROR, "{:?}", error);
-            return Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body(ApiError::AccountNotFound.to_string())
-                .unwrap();
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiError::DatabaseError(error.to_string()).to_string(),
+            )
         }
     }
 }
 
 /// Function to update an account in the "database"
+///
+/// Callers may only update their own account, unless they are an admin
 #[instrument]
-#[utoipa::path(put, path = "/account", responses((
+#[utoipa::path(put, path = "/account", tag = "Account", responses((
     status = 200,
     description = "Account updated",
     body = None
 ),
-(status = 404, description = "Account not found", body = ApiError)))]
+(status = 400, description = "Invalid email or weak password", body = ApiError, example = json!({"status": 400, "error": "Invalid account: password must contain at least one letter and one digit"})),
+(status = 401, description = "Login required", body = AuthError, example = json!({"status": 401, "error": "Invalid token"})),
+(status = 403, description = "Caller does not own the account", body = ApiError, example = json!({"status": 403, "error": "Only the account itself or an admin can change it"})),
+(status = 404, description = "Account not found, e.g. renamed by another request in the meantime", body = ApiError, example = json!({"status": 404, "error": "Account not found"})),
+(status = 409, description = "Another account already has the new email", body = ApiError, example = json!({"status": 409, "error": "An account with this email already exists"})),
+(status = 500, description = "Failed to update the account", body = ApiError, example = json!({"status": 500, "error": "Database error: connection refused"}))))]
 pub async fn put_account(
     State(state): State<AppState>,
+    claims: Claims,
     Query(UserAccountInfo { email, password }): Query<UserAccountInfo>,
     Json(account): Json<Account>,
 ) -> impl IntoResponse {
     let email = match email {
         Some(email) => email,
         None => {
-            return Response::builder()
-                .status(StatusCode::BAD_REQUEST)
-                .body(ApiError::MissingParameters.to_string())
-                .unwrap();
+            return json_error(
+                StatusCode::BAD_REQUEST,
+                ApiError::MissingParameters.to_string(),
+            );
         }
     };
-    match state.update_account(&email, account).await {
-        Ok(_) => Response::builder()
-            .status(StatusCode::OK)
-            .body("Account updated".to_string())
-            .unwrap(),
+    if let Err(error) = account.validate() {
+        return json_error(StatusCode::BAD_REQUEST, error.to_string());
+    }
+    let account_id = match owned_account_id(&state, &claims, &email).await {
+        Ok(account_id) => account_id,
+        Err(error) => return json_error(error.status(), error),
+    };
+    match state
+        .update_account(&account_id, account, claims.account_id)
+        .await
+    {
+        // The account was deleted by a concurrent request since it was looked up
+        Ok(0) => json_error(StatusCode::NOT_FOUND, ApiError::AccountNotFound.to_string()),
+        Ok(_) => text_response(StatusCode::OK, "Account updated"),
+        Err(error) if is_unique_violation(error.as_ref()) => {
+            json_error(StatusCode::CONFLICT, ApiError::EmailTaken.to_string())
+        }
         Err(error) => {
             tracing::event!(tracing::Level::ERROR, "{:?}", error);
-            return Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body(ApiError::AccountNotFound.to_string())
-                .unwrap();
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiError::DatabaseError(error.to_string()).to_string(),
+            )
         }
     }
 }
 
-/// Function to get an answer from the "database"
+/// Function to look up the account with this email, making sure the caller may change it
+///
+/// Only the account itself, by the id its login is linked to, or an admin may change an account.
+/// Returns the account's id, so the change can go by id and not by an email that may have changed since
+async fn owned_account_id(
+    state: &AppState,
+    claims: &Claims,
+    email: &str,
+) -> Result<AccountId, ApiError> {
+    let account = state
+        .get_account(email)
+        .await
+        .map_err(|error| ApiError::DatabaseError(error.to_string()))?
+        .ok_or(ApiError::AccountNotFound)?;
+    if !claims.is_admin && claims.account_id != Some(account.id.0) {
+        return Err(ApiError::NotAccountOwner);
+    }
+    Ok(account.id)
+}
+
+/// Function to get the account of the logged in user from the "database"
 #[instrument]
-#[utoipa::path(get, path = "/answers", responses((
+#[utoipa::path(get, path = "/me", tag = "Account", responses((
     status = 200,
-    description = "Returns all answers for a question",
-    body = None
+    description = "Returns the account of the logged in user",
+    body = PublicAccount
 ),
-(status = 404, description = "Question not found", body = ApiError)))]
-pub async fn get_answers(
-    State(state): State<AppState>,
-    Query(IdParam { id }): Query<IdParam>,
-) -> impl IntoResponse {
-    let question_id = QuestionId(id.unwrap());
-    match state.get_answers(&question_id).await {
-        Ok(answer) => Response::builder()
-            .status(StatusCode::OK)
-            .body(serde_json::to_string_pretty(&answer).unwrap())
-            .unwrap(),
+(status = 404, description = "Account not found", body = ApiError, example = json!({"status": 404, "error": "Account not found"}))))]
+pub async fn get_me(State(state): State<AppState>, claims: Claims) -> impl IntoResponse {
+    match state.get_account(&claims.email).await {
+        Ok(Some(account)) => json_response(
+            StatusCode::OK,
+            serde_json::to_string_pretty(&PublicAccount::from(account)).unwrap(),
+        ),
+        Ok(None) => json_error(StatusCode::NOT_FOUND, ApiError::AccountNotFound.to_string()),
         Err(error) => {
             tracing::event!(tracing::Level::ERROR, "{:?}", error);
-            Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body(ApiError::AnswerNotFound.to_string())
-                .unwrap()
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiError::DatabaseError(error.to_string()).to_string(),
+            )
+        }
+    }
+}
+
+/// A parameter struct for searching, accounts by email or questions and answers by their text
+///
+/// ##Example:
+/// ```
+/// {
+///  "q": "pdx.edu"
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SearchParam {
+    pub q: Option<String>,
+}
+
+/// Function to search the accounts whose email contains the given text, ignoring case
+///
+/// Pages with `limit` and `offset` like the question lists, passwords are never returned
+#[instrument]
+#[utoipa::path(get, path = "/accounts/search", tag = "Account", responses((
+    status = 200,
+    description = "Returns a page of the matching accounts",
+    body = [PublicAccount]
+),
+(status = 400, description = "Missing search text", body = ApiError, example = json!({"status": 400, "error": "Missing parameter"})),
+(status = 403, description = "Caller is not an admin", body = AuthError, example = json!({"status": 403, "error": "Admin required"}))))]
+pub async fn search_accounts(
+    State(state): State<AppState>,
+    _admin: AdminClaims,
+    Query(SearchParam { q }): Query<SearchParam>,
+    Query(Pagination { limit, offset, .. }): Query<Pagination>,
+) -> impl IntoResponse {
+    let q = match q.filter(|q| !q.trim().is_empty()) {
+        Some(q) => q,
+        None => return json_error(StatusCode::BAD_REQUEST, ApiError::MissingParameters),
+    };
+    let (limit, clamped) = state.2.page_size(limit);
+    let offset = offset.unwrap_or(0).max(0);
+    match state.search_accounts(q.trim(), limit, offset).await {
+        Ok(accounts) => page_response(
+            serde_json::to_string_pretty(&accounts).unwrap(),
+            limit,
+            clamped,
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, error)
+        }
+    }
+}
+
+/// Function to search the questions and answers for text, ignoring case
+///
+/// Each section holds at most `limit` results, the configured page size when not given
+#[instrument]
+#[utoipa::path(get, path = "/search", tag = "Question", responses((
+    status = 200,
+    description = "Returns the matching questions and the matching answers",
+    body = SearchResults
+),
+(status = 400, description = "Missing search text", body = ApiError, example = json!({"status": 400, "error": "Missing parameter"})),
+(status = 500, description = "Failed to search", body = ApiError, example = json!({"status": 500, "error": "Database error: connection refused"}))))]
+pub async fn get_search(
+    State(state): State<AppState>,
+    Query(SearchParam { q }): Query<SearchParam>,
+    Query(Pagination { limit, .. }): Query<Pagination>,
+) -> impl IntoResponse {
+    let q = match q.filter(|q| !q.trim().is_empty()) {
+        Some(q) => q,
+        None => return json_error(StatusCode::BAD_REQUEST, ApiError::MissingParameters),
+    };
+    let (limit, clamped) = state.2.page_size(limit);
+    match state.global_search(q.trim(), limit).await {
+        Ok(results) => page_response(
+            serde_json::to_string_pretty(&results).unwrap(),
+            limit,
+            clamped,
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, error)
+        }
+    }
+}
+
+/// Function to get an account by its id, without its password
+#[instrument]
+#[utoipa::path(get, path = "/accounts/:id", tag = "Account", responses((
+    status = 200,
+    description = "Returns the account",
+    body = PublicAccount
+),
+(status = 403, description = "Caller is not an admin", body = AuthError, example = json!({"status": 403, "error": "Admin required"})),
+(status = 400, description = "Invalid account id", body = ApiError, example = json!({"status": 400, "error": "Invalid account id"})),
+(status = 404, description = "Account not found", body = ApiError, example = json!({"status": 404, "error": "Account not found"}))))]
+pub async fn get_account_by_id(
+    State(state): State<AppState>,
+    _admin: AdminClaims,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let account_id = match id.parse::<AccountId>() {
+        Ok(account_id) => account_id,
+        Err(_) => return json_error(StatusCode::BAD_REQUEST, ApiError::InvalidAccountId),
+    };
+    match state.get_account_by_id(&account_id).await {
+        Ok(Some(account)) => json_response(
+            StatusCode::OK,
+            serde_json::to_string_pretty(&PublicAccount::from(account)).unwrap(),
+        ),
+        Ok(None) => json_error(StatusCode::NOT_FOUND, ApiError::AccountNotFound.to_string()),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiError::DatabaseError(error.to_string()).to_string(),
+            )
+        }
+    }
+}
+
+/// Function to make an account an admin, only an admin may do so
+#[instrument]
+#[utoipa::path(post, path = "/accounts/:id/promote", tag = "Account", responses((
+    status = 200,
+    description = "Account promoted to admin",
+    body = None
+),
+(status = 400, description = "Invalid account id", body = ApiError, example = json!({"status": 400, "error": "Invalid account id"})),
+(status = 403, description = "Caller is not an admin", body = AuthError, example = json!({"status": 403, "error": "Admin required"})),
+(status = 404, description = "Account not found", body = ApiError, example = json!({"status": 404, "error": "Account not found"}))))]
+pub async fn post_promote_account(
+    State(state): State<AppState>,
+    AdminClaims(admin): AdminClaims,
+    Path(id): Path<String>,
+) -> impl IntoResponse {
+    let account_id = match id.parse::<AccountId>() {
+        Ok(account_id) => account_id,
+        Err(_) => return json_error(StatusCode::BAD_REQUEST, ApiError::InvalidAccountId),
+    };
+    match state.promote_account(&account_id, admin.account_id).await {
+        Ok(0) => json_error(StatusCode::NOT_FOUND, ApiError::AccountNotFound),
+        Ok(_) => text_response(StatusCode::OK, "Account promoted"),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, error)
+        }
+    }
+}
+
+/// Function to pin a question to the top of the question lists, only an admin may do so
+#[instrument]
+#[utoipa::path(post, path = "/questions/:id/pin", tag = "Question", responses((
+    status = 200,
+    description = "Question pinned",
+    body = None
+),
+(status = 403, description = "Caller is not an admin", body = AuthError, example = json!({"status": 403, "error": "Admin required"})),
+(status = 404, description = "Question not found", body = ApiError, example = json!({"status": 404, "error": "Question not found"}))))]
+pub async fn post_pin_question(
+    State(state): State<AppState>,
+    AdminClaims(admin): AdminClaims,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    set_question_pinned(state, QuestionId(id), true, admin.account_id).await
+}
+
+/// Function to unpin a question, putting it back in its usual place in the lists, only an admin may do so
+#[instrument]
+#[utoipa::path(post, path = "/questions/:id/unpin", tag = "Question", responses((
+    status = 200,
+    description = "Question unpinned",
+    body = None
+),
+(status = 403, description = "Caller is not an admin", body = AuthError, example = json!({"status": 403, "error": "Admin required"})),
+(status = 404, description = "Question not found", body = ApiError, example = json!({"status": 404, "error": "Question not found"}))))]
+pub async fn post_unpin_question(
+    State(state): State<AppState>,
+    AdminClaims(admin): AdminClaims,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    set_question_pinned(state, QuestionId(id), false, admin.account_id).await
+}
+
+/// A parameter struct for the question another question duplicates
+///
+/// ##Example:
+/// ```
+/// {
+///  "target": "3"
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct DuplicateParam {
+    pub target: Option<i32>,
+}
+
+/// Function to mark a question as a duplicate of another question, only an admin may do so
+///
+/// Marking it again points it at the new target instead
+#[instrument]
+#[utoipa::path(post, path = "/questions/:id/mark-duplicate", tag = "Question", responses((
+    status = 200,
+    description = "Question marked as a duplicate",
+    body = None
+),
+(status = 400, description = "Missing target or the question would duplicate itself", body = ApiError, example = json!({"status": 400, "error": "Invalid duplicate: a question can not duplicate itself"})),
+(status = 403, description = "Caller is not an admin", body = AuthError, example = json!({"status": 403, "error": "Admin required"})),
+(status = 404, description = "Question or target not found", body = ApiError, example = json!({"status": 404, "error": "Question not found"}))))]
+pub async fn post_mark_duplicate(
+    State(state): State<AppState>,
+    AdminClaims(admin): AdminClaims,
+    Path(id): Path<i32>,
+    Query(DuplicateParam { target }): Query<DuplicateParam>,
+) -> impl IntoResponse {
+    let target = match target {
+        Some(target) => QuestionId(target),
+        None => return json_error(StatusCode::BAD_REQUEST, ApiError::MissingParameters),
+    };
+    if target.0 == id {
+        return json_error(
+            StatusCode::BAD_REQUEST,
+            ApiError::InvalidDuplicate("a question can not duplicate itself".to_string()),
+        );
+    }
+    match state.get_question(&target).await {
+        Ok(Some(_)) => (),
+        Ok(None) => return json_error(StatusCode::NOT_FOUND, ApiError::QuestionNotFound),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, error);
+        }
+    }
+    match state
+        .mark_duplicate(&QuestionId(id), &target, admin.account_id)
+        .await
+    {
+        Ok(0) => json_error(StatusCode::NOT_FOUND, ApiError::QuestionNotFound),
+        Ok(_) => text_response(
+            StatusCode::OK,
+            format!("Question marked as a duplicate of #{}", target.0),
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, error)
+        }
+    }
+}
+
+/// A close param struct, why a question is being closed
+///
+/// ##Example:
+/// ```
+/// {
+///  "reason": "off-topic"
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CloseParam {
+    pub reason: Option<CloseReason>,
+}
+
+/// Function to close a question with a reason, only an admin may do so
+///
+/// The reason is one of duplicate, off-topic, unclear or resolved. Returns the closed question
+#[instrument]
+#[utoipa::path(post, path = "/questions/:id/close", tag = "Question", responses((
+    status = 200,
+    description = "Question closed, returns the question",
+    body = Question
+),
+(status = 400, description = "Missing or unknown reason", body = ApiError, example = json!({"status": 400, "error": "Missing parameter"})),
+(status = 403, description = "Caller is not an admin", body = AuthError, example = json!({"status": 403, "error": "Admin required"})),
+(status = 404, description = "Question not found", body = ApiError, example = json!({"status": 404, "error": "Question not found"}))))]
+pub async fn post_close_question(
+    State(state): State<AppState>,
+    AdminClaims(admin): AdminClaims,
+    Path(id): Path<i32>,
+    Query(CloseParam { reason }): Query<CloseParam>,
+) -> impl IntoResponse {
+    let reason = match reason {
+        Some(reason) => reason,
+        None => return json_error(StatusCode::BAD_REQUEST, ApiError::MissingParameters),
+    };
+    let question_id = QuestionId(id);
+    match state
+        .close_question(&question_id, reason, admin.account_id)
+        .await
+    {
+        Ok(0) => return json_error(StatusCode::NOT_FOUND, ApiError::QuestionNotFound),
+        Ok(_) => (),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, error);
+        }
+    }
+    match state.get_question(&question_id).await {
+        Ok(Some(question)) => json_response(
+            StatusCode::OK,
+            serde_json::to_string_pretty(&question).unwrap(),
+        ),
+        Ok(None) => json_error(StatusCode::NOT_FOUND, ApiError::QuestionNotFound),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, error)
+        }
+    }
+}
+
+/// Function shared by the pin and unpin handlers
+async fn set_question_pinned(
+    state: AppState,
+    question_id: QuestionId,
+    pinned: bool,
+    actor_id: Option<i32>,
+) -> Response<String> {
+    match state
+        .set_question_pinned(&question_id, pinned, actor_id)
+        .await
+    {
+        Ok(0) => json_error(StatusCode::NOT_FOUND, ApiError::QuestionNotFound),
+        Ok(_) => text_response(
+            StatusCode::OK,
+            if pinned {
+                "Question pinned"
+            } else {
+                "Question unpinned"
+            },
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, error)
+        }
+    }
+}
+
+/// Function to get an answer from the "database"
+///
+/// With `?question_ids=1,2,3` the answers of all those questions are returned in one flat list,
+/// each answer carrying its `question_id`, instead of a page of the answers of `?id=`
+#[instrument]
+#[utoipa::path(get, path = "/answers", tag = "Answer", responses((
+    status = 200,
+    description = "Returns all answers for a question, or for each of the given questions",
+    body = [Answer]
+),
+(status = 400, description = "Missing or invalid question ids", body = ApiError, example = json!({"status": 400, "error": "Invalid question ids: at most 50 ids are allowed"})),
+(status = 404, description = "Question not found", body = ApiError, example = json!({"status": 404, "error": "Question not found"})),
+(status = 500, description = "Failed to get answers", body = ApiError, example = json!({"status": 500, "error": "Database error: connection refused"}))))]
+pub async fn get_answers(
+    State(state): State<AppState>,
+    Query(IdParam { id }): Query<IdParam>,
+    Query(QuestionIdsParam { question_ids }): Query<QuestionIdsParam>,
+    Query(AnswerPagination {
+        limit,
+        offset,
+        sort,
+    }): Query<AnswerPagination>,
+) -> impl IntoResponse {
+    if let Some(question_ids) = question_ids {
+        let question_ids = match parse_question_ids(&question_ids) {
+            Ok(question_ids) => question_ids,
+            Err(error) => {
+                return json_error(StatusCode::BAD_REQUEST, error.to_string());
+            }
+        };
+        return match state.get_answers_for_questions(&question_ids).await {
+            Ok(answers) => json_response(
+                StatusCode::OK,
+                serde_json::to_string_pretty(&answers).unwrap(),
+            ),
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                json_error(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    ApiError::DatabaseError(error.to_string()).to_string(),
+                )
+            }
+        };
+    }
+    let question_id = match id {
+        Some(id) => QuestionId(id),
+        None => {
+            return json_error(
+                StatusCode::BAD_REQUEST,
+                ApiError::MissingParameters.to_string(),
+            );
+        }
+    };
+    // An unknown question is a 404, a question with no answers yet is an empty page
+    match state.question_exists(&question_id).await {
+        Ok(true) => (),
+        Ok(false) => return json_error(StatusCode::NOT_FOUND, ApiError::QuestionNotFound),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, error);
+        }
+    }
+    let (limit, clamped) = state.2.page_size(limit);
+    let offset = offset.unwrap_or(0).max(0);
+    let sort = sort.unwrap_or(state.2.answer_sort_default);
+    match state.get_answers(&question_id, limit, offset, sort).await {
+        Ok(answer) => page_response(
+            serde_json::to_string_pretty(&answer).unwrap(),
+            limit,
+            clamped,
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, error)
+        }
+    }
+}
+
+/// Function to count the answers of a question, without fetching them
+#[instrument]
+#[utoipa::path(get, path = "/answers/count", tag = "Answer", responses((
+    status = 200,
+    description = "Returns the number of answers the question has",
+    body = AnswerCount
+),
+(status = 400, description = "Missing question id", body = ApiError, example = json!({"status": 400, "error": "Missing parameter"})),
+(status = 500, description = "Failed to count answers", body = ApiError, example = json!({"status": 500, "error": "Database error: connection refused"}))))]
+pub async fn get_answer_count(
+    State(state): State<AppState>,
+    Query(IdParam { id }): Query<IdParam>,
+) -> impl IntoResponse {
+    let question_id = match id {
+        Some(id) => QuestionId(id),
+        None => {
+            return json_error(
+                StatusCode::BAD_REQUEST,
+                ApiError::MissingParameters.to_string(),
+            );
+        }
+    };
+    match state.count_answers(&question_id).await {
+        Ok(count) => json_response(
+            StatusCode::OK,
+            serde_json::to_string_pretty(&AnswerCount { count }).unwrap(),
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiError::DatabaseError(error.to_string()).to_string(),
+            )
+        }
+    }
+}
+
+/// Function to stream the answers posted to a question from now on, as server-sent events
+///
+/// Every new answer is sent as an `answer` event with the answer as JSON data
+#[instrument]
+#[utoipa::path(get, path = "/questions/:id/answers/stream", tag = "Answer", responses((
+    status = 200,
+    description = "A stream of `answer` events, one per answer posted to the question",
+    content_type = "text/event-stream",
+    body = Answer
+),
+(status = 404, description = "Question not found", body = ApiError, example = json!({"status": 404, "error": "Question not found"})),
+(status = 500, description = "Failed to look the question up", body = ApiError, example = json!({"status": 500, "error": "Database error: connection refused"}))))]
+pub async fn get_answer_stream(State(state): State<AppState>, Path(id): Path<i32>) -> Response {
+    let question_id = QuestionId(id);
+    match state.question_exists(&question_id).await {
+        Ok(true) => (),
+        Ok(false) => {
+            return json_error(StatusCode::NOT_FOUND, ApiError::QuestionNotFound).into_response();
+        }
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiError::DatabaseError(error.to_string()),
+            )
+            .into_response();
+        }
+    }
+    let answers = state
+        .3
+        .subscribe(&question_id)
+        // A subscriber that fell behind just misses the answers it lagged on
+        .filter_map(|answer| answer.ok())
+        .map(|answer| Event::default().event("answer").json_data(answer));
+    Sse::new(answers)
+        .keep_alive(KeepAlive::default())
+        .into_response()
+}
+
+/// Function to get a question along with its answers
+///
+/// If the request carries a valid token, `answered_by_me` says whether the caller already answered it
+#[instrument]
+#[utoipa::path(get, path = "/question/answers", tag = "Question", responses((
+    status = 200,
+    description = "Returns the question with its answers",
+    body = QuestionWithAnswers
+),
+(status = 404, description = "Question not found", body = ApiError, example = json!({"status": 404, "error": "Question not found"}))))]
+pub async fn get_question_with_answers(
+    State(state): State<AppState>,
+    claims: Option<Claims>,
+    Query(IdParam { id }): Query<IdParam>,
+) -> impl IntoResponse {
+    let question_id = match id {
+        Some(id) => QuestionId(id),
+        None => {
+            return json_error(
+                StatusCode::BAD_REQUEST,
+                ApiError::MissingParameters.to_string(),
+            );
+        }
+    };
+    let account_id = claims.and_then(|claims| claims.account_id);
+    match state
+        .get_question_with_answers(&question_id, account_id)
+        .await
+    {
+        Ok(bundle) => json_response(
+            StatusCode::OK,
+            serde_json::to_string_pretty(&bundle).unwrap(),
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(
+                StatusCode::NOT_FOUND,
+                ApiError::QuestionNotFound.to_string(),
+            )
         }
     }
 }
 
 /// Function to delete an answer from the "database"
 #[instrument]
-#[utoipa::path(delete, path = "/answers/:id", responses((
+#[utoipa::path(delete, path = "/answers/:id", tag = "Answer", responses((
     status = 200,
     description = "Answer deleted",
     body = None
 ),
-(status = 500, description = "Failed to delete answer", body = ApiError)))]
+(status = 404, description = "Answer not found", body = ApiError, example = json!({"status": 404, "error": "Answer not found"})),
+(status = 500, description = "Failed to delete answer", body = ApiError, example = json!({"status": 500, "error": "Failed to delete answer"}))))]
 pub async fn delete_answer(
     State(state): State<AppState>,
+    claims: Option<Claims>,
     Query(IdParam { id }): Query<IdParam>,
 ) -> impl IntoResponse {
-    let answer_id = QuestionId(id.unwrap());
-    match state.delete_answer(&answer_id).await {
-        Ok(_) => Response::builder()
-            .status(StatusCode::OK)
-            .body("Answer deleted".to_string())
-            .unwrap(),
+    let answer_id = match id {
+        Some(id) => AnswerId(id),
+        None => return json_error(StatusCode::BAD_REQUEST, ApiError::MissingParameters),
+    };
+    match state.answer_exists(&answer_id).await {
+        Ok(true) => (),
+        Ok(false) => {
+            return json_error(StatusCode::NOT_FOUND, ApiError::AnswerNotFound.to_string());
+        }
         Err(error) => {
             tracing::event!(tracing::Level::ERROR, "{:?}", error);
-            Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body("Failed to delete answer".to_string())
-                .unwrap()
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, error.to_string());
+        }
+    }
+    let actor_id = claims.and_then(|claims| claims.account_id);
+    match state.delete_answer(&answer_id, actor_id).await {
+        Ok(0) => json_error(StatusCode::NOT_FOUND, ApiError::AnswerNotFound.to_string()),
+        Ok(_) => text_response(StatusCode::OK, "Answer deleted"),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                "Failed to delete answer".to_string(),
+            )
         }
     }
 }
 
 /// Function to update an answer in the "database"
+///
+/// `?id=` is the id of the answer, it is updated and returned as it now is.
+/// Only the answer's author or an admin may edit it, and with ANSWER_EDIT_WINDOW_SECS set,
+/// answers older than the window can only be edited by an admin
 #[instrument]
-#[utoipa::path(put, path = "/answers/:id", responses((
+#[utoipa::path(put, path = "/answers/:id", tag = "Answer", request_body = AnswerBody, responses((
     status = 200,
-    description = "Answer updated",
-    body = None
+    description = "Answer updated, returns the updated answer",
+    body = Answer
 ),
-(status = 500, description = "Failed to update answer", body = ApiError)))]
+(status = 400, description = "Content too profane", body = ApiError, example = json!({"status": 400, "error": "Content too profane"})),
+(status = 401, description = "Login required", body = ApiError, example = json!({"status": 401, "error": "Login required"})),
+(status = 403, description = "Caller did not write the answer, or the edit window expired", body = ApiError, example = json!({"status": 403, "error": "Edit window expired"})),
+(status = 404, description = "Answer not found", body = ApiError, example = json!({"status": 404, "error": "Answer not found"})),
+(status = 500, description = "Failed to update answer", body = ApiError, example = json!({"status": 500, "error": "Failed to update answer"}))))]
 pub async fn put_answer(
     State(state): State<AppState>,
+    claims: Option<Claims>,
     Query(IdParam { id }): Query<IdParam>,
-    Json(answer): Json<Answer>,
+    StrictJson(answer): StrictJson<AnswerBody>,
 ) -> impl IntoResponse {
-    let answer_id = QuestionId(id.unwrap());
+    let answer_id = match id {
+        Some(id) => AnswerId(id),
+        None => return json_error(StatusCode::BAD_REQUEST, ApiError::MissingParameters),
+    };
+    let author_id = match state.answer_author(&answer_id).await {
+        Ok(Some(author_id)) => author_id,
+        Ok(None) => {
+            return json_error(StatusCode::NOT_FOUND, ApiError::AnswerNotFound.to_string());
+        }
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, error.to_string());
+        }
+    };
+    let is_admin = claims.as_ref().is_some_and(|claims| claims.is_admin);
+    if !is_admin {
+        // Anonymous answers have no author to match, so only an admin can edit them
+        let error = match &claims {
+            None => Some(ApiError::LoginRequired),
+            Some(claims) if author_id.is_none() || claims.account_id != author_id => {
+                Some(ApiError::NotAnswerAuthor)
+            }
+            Some(_) => None,
+        };
+        if let Some(error) = error {
+            return json_error(error.status(), error);
+        }
+    }
+    if let Some(window) = state.2.answer_edit_window_secs.filter(|_| !is_admin) {
+        match state.answer_edit_window_expired(&answer_id, window).await {
+            Ok(false) => (),
+            Ok(true) => {
+                let error = ApiError::EditWindowExpired;
+                return json_error(error.status(), error);
+            }
+            Err(error) => {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+                return json_error(StatusCode::INTERNAL_SERVER_ERROR, error.to_string());
+            }
+        }
+    }
+    let content = match censor(&state.2, &state.5, "content", answer.content).await {
+        Ok(content) => content,
+        Err(error) => return json_error(error.status(), error),
+    };
     let answer = Answer {
-        question_id: answer.question_id,
-        content: check_profanity(answer.content).await.unwrap(),
+        content,
+        ..Answer::from(answer)
     };
-    match state.update_answer(&answer_id, answer).await {
-        Ok(_) => Response::builder()
-            .status(StatusCode::OK)
-            .body("Answer updated".to_string())
-            .unwrap(),
+    let actor_id = claims.and_then(|claims| claims.account_id);
+    match state.update_answer(&answer_id, answer, actor_id).await {
+        // Deleted by a concurrent request since the existence check
+        Ok(None) => json_error(StatusCode::NOT_FOUND, ApiError::AnswerNotFound.to_string()),
+        Ok(Some(answer)) => json_response(
+            StatusCode::OK,
+            serde_json::to_string_pretty(&answer).unwrap(),
+        ),
         Err(error) => {
             tracing::event!(tracing::Level::ERROR, "{:?}", error);
-            Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(error.to_string())
-                .unwrap()
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, error.to_string())
         }
     }
 }
 
 /// Function to create an answer in the "database"
+///
+/// Deprecated: the question comes from the body here, use `POST /questions/:id/answers` instead.
+/// Responses carry a `Deprecation` header pointing there
 #[instrument]
-#[utoipa::path(post, path = "/answers", responses((
-    status = 200,
-    description = "Answer added",
-    body = None
+#[utoipa::path(post, path = "/answers", tag = "Answer", request_body = AnswerBody, responses((
+    status = 201,
+    description = "Answer added, returns the new answer. Deprecated in favour of POST /questions/:id/answers",
+    body = Answer
 ),
-(status = 500, description = "Failed to add answer", body = ApiError)))]
+(status = 400, description = "Parent answer belongs to another question, or content too profane", body = ApiError, example = json!({"status": 400, "error": "Parent answer belongs to another question"})),
+(status = 404, description = "Question or parent answer not found", body = ApiError, example = json!({"status": 404, "error": "Question not found"})),
+(status = 409, description = "Answer limit reached, or the same answer was already posted with DEDUPE_ANSWERS on", body = ApiError, example = json!({"status": 409, "error": "Answer limit reached"})),
+(status = 500, description = "Failed to add answer", body = ApiError, example = json!({"status": 500, "error": "Failed to add answer"}))))]
 pub async fn post_answer(
     State(state): State<AppState>,
-    Json(answer): Json<Answer>,
+    claims: Option<Claims>,
+    StrictJson(answer): StrictJson<AnswerBody>,
+) -> impl IntoResponse {
+    let question_id = answer.question_id.0;
+    let mut response = create_answer(state, claims, Answer::from(answer)).await;
+    let headers = response.headers_mut();
+    headers.insert("Deprecation", HeaderValue::from_static("true"));
+    if let Ok(link) = HeaderValue::from_str(&format!(
+        "</questions/{}/answers>; rel=\"successor-version\"",
+        question_id
+    )) {
+        headers.insert("Link", link);
+    }
+    response
+}
+
+/// A new answer struct, the body for posting an answer to the question in the path
+///
+/// ##Example:
+/// ```
+/// {
+///  "content": "This is an answer to the question",
+///  "parent_answer_id": 2
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NewAnswer {
+    #[schema(example = "This is an **answer** to the question")]
+    pub content: String,
+    #[schema(value_type = Option<i32>, example = 2)]
+    #[serde(default)]
+    pub parent_answer_id: Option<AnswerId>,
+}
+
+/// Function to answer the question in the path
+///
+/// The question comes from the path only, so an answer can not end up on another question than intended
+#[instrument]
+#[utoipa::path(post, path = "/questions/:id/answers", tag = "Answer", request_body = NewAnswer, responses((
+    status = 201,
+    description = "Answer added, returns the new answer",
+    body = Answer
+),
+(status = 400, description = "Parent answer belongs to another question, or content too profane", body = ApiError, example = json!({"status": 400, "error": "Parent answer belongs to another question"})),
+(status = 404, description = "Question or parent answer not found", body = ApiError, example = json!({"status": 404, "error": "Question not found"})),
+(status = 409, description = "Answer limit reached, or the same answer was already posted with DEDUPE_ANSWERS on", body = ApiError, example = json!({"status": 409, "error": "Answer limit reached"})),
+(status = 500, description = "Failed to add answer", body = ApiError, example = json!({"status": 500, "error": "Failed to add answer"}))))]
+pub async fn post_question_answer(
+    State(state): State<AppState>,
+    claims: Option<Claims>,
+    Path(id): Path<i32>,
+    StrictJson(new_answer): StrictJson<NewAnswer>,
 ) -> impl IntoResponse {
     let answer = Answer {
-        question_id: answer.question_id,
-        content: check_profanity(answer.content).await.unwrap(),
+        id: None,
+        content: new_answer.content,
+        content_html: None,
+        question_id: QuestionId(id),
+        parent_answer_id: new_answer.parent_answer_id,
+        created_at: None,
+        is_accepted: false,
+        author_name: None,
+    };
+    create_answer(state, claims, answer).await
+}
+
+/// Function shared by both ways of posting an answer, checking and adding it and streaming it out
+async fn create_answer(
+    state: AppState,
+    claims: Option<Claims>,
+    answer: Answer,
+) -> Response<String> {
+    // Censored up front so the transaction is not held open across the call to the profanity API
+    let content = match censor(&state.2, &state.5, "content", answer.content).await {
+        Ok(content) => content,
+        Err(error) => return json_error(error.status(), error),
+    };
+    let answer = Answer { content, ..answer };
+    let author_id = claims.and_then(|claims| claims.account_id);
+    match add_answer_checked(&state, &answer, author_id).await {
+        Ok(answer) => {
+            state.3.publish(&answer);
+            json_response(
+                StatusCode::CREATED,
+                serde_json::to_string_pretty(&answer).unwrap(),
+            )
+        }
+        Err(error) => {
+            if let ApiError::DatabaseError(_) = error {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            }
+            json_error(error.status(), error)
+        }
+    }
+}
+
+/// Function to check an answer and add it in one transaction
+///
+/// The question stays locked from the checks until the answer is in, so answers posted at the same
+/// time can not push a question past its answer limit. Any failed step rolls the whole thing back
+async fn add_answer_checked(
+    state: &AppState,
+    answer: &Answer,
+    author_id: Option<i32>,
+) -> Result<Answer, ApiError> {
+    let mut tx = state.begin().await?;
+    if !tx.lock_question(&answer.question_id).await? {
+        return Err(ApiError::QuestionNotFound);
+    }
+    if tx.question_closed(&answer.question_id).await? {
+        return Err(ApiError::QuestionClosed);
+    }
+    if tx
+        .answer_limit_reached(&answer.question_id, state.2.max_answers_per_question)
+        .await?
+    {
+        return Err(ApiError::AnswerLimitReached);
+    }
+    // Catches double submits, the question lock keeps two at once from both getting through
+    if state.2.dedupe_answers
+        && tx
+            .has_duplicate_answer(&answer.question_id, &answer.content)
+            .await?
+    {
+        return Err(ApiError::DuplicateAnswer);
+    }
+    // A reply has to stay on the same question as the answer it replies to
+    if let Some(parent_answer_id) = &answer.parent_answer_id {
+        match tx.get_answer_question(parent_answer_id).await? {
+            Some(parent_question_id) if parent_question_id == answer.question_id => (),
+            Some(_) => return Err(ApiError::ParentAnswerMismatch),
+            None => return Err(ApiError::AnswerNotFound),
+        }
+    }
+    let answer = tx.add_answer(answer, author_id).await?;
+    tx.commit().await?;
+    Ok(answer)
+}
+
+/// A question and answer import struct, the body for adding a question together with its answers
+///
+/// ##Example:
+/// ```
+/// {
+///  "question": { "id": 0, "title": "What is rust?", "content": "Is it a language?", "tags": ["rust"] },
+///  "answers": [{ "content": "Yes, a systems programming language" }]
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NewQa {
+    pub question: NewQuestion,
+    pub answers: Vec<NewAnswer>,
+}
+
+/// A created question and answers struct, the ids given to an imported question and its answers
+///
+/// ##Example:
+/// ```
+/// {
+///  "question_id": 1,
+///  "answer_ids": [1, 2]
+/// }
+/// ```
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QaCreated {
+    #[schema(value_type = i32, example = 1)]
+    pub question_id: QuestionId,
+    #[schema(value_type = Vec<i32>, example = json!([1, 2]))]
+    pub answer_ids: Vec<AnswerId>,
+}
+
+/// Function to add a question and its answers at once, for importing a question and answer pair
+///
+/// Everything is added in one transaction, so if any part fails nothing is added
+#[instrument]
+#[utoipa::path(post, path = "/qa", tag = "Question", request_body = NewQa, responses((
+    status = 201,
+    description = "Question and answers added, returns their ids",
+    body = QaCreated
+),
+(status = 400, description = "Too many or too long tags, an answer replies to another answer, or content too profane", body = ApiError, example = json!({"status": 400, "error": "Invalid tags: at most 10 tags are allowed"})),
+(status = 401, description = "Login required", body = AuthError, example = json!({"status": 401, "error": "Missing credentials"})),
+(status = 409, description = "More answers than a question may have", body = ApiError, example = json!({"status": 409, "error": "Answer limit reached"})),
+(status = 500, description = "Failed to add the question and answers", body = ApiError, example = json!({"status": 500, "error": "Database error: connection refused"}))))]
+pub async fn post_qa(
+    State(state): State<AppState>,
+    claims: Claims,
+    StrictJson(qa): StrictJson<NewQa>,
+) -> impl IntoResponse {
+    if let Err(error) = question::validate_tags(&qa.question.tags) {
+        return json_error(StatusCode::BAD_REQUEST, error.to_string());
+    }
+    // The question is new, so there is no answer on it yet that one of these could reply to
+    if qa
+        .answers
+        .iter()
+        .any(|answer| answer.parent_answer_id.is_some())
+    {
+        return json_error(StatusCode::BAD_REQUEST, ApiError::ParentAnswerMismatch);
+    }
+    if qa.answers.len() as i64 > state.2.max_answers_per_question {
+        return json_error(StatusCode::CONFLICT, ApiError::AnswerLimitReached);
+    }
+    let question = Question::from(qa.question);
+    // Censored up front so the transaction is not held open across the calls to the profanity API
+    let title = match censor(&state.2, &state.5, "title", question.title.clone()).await {
+        Ok(title) => title,
+        Err(error) => return json_error(error.status(), error),
+    };
+    let content = match censor(&state.2, &state.5, "content", question.content.clone()).await {
+        Ok(content) => content,
+        Err(error) => return json_error(error.status(), error),
+    };
+    let question = Question {
+        title,
+        content,
+        ..question
+    };
+    let mut contents = Vec::with_capacity(qa.answers.len());
+    for answer in qa.answers {
+        let content = match censor(&state.2, &state.5, "content", answer.content).await {
+            Ok(content) => content,
+            Err(error) => return json_error(error.status(), error),
+        };
+        contents.push(content);
+    }
+    match add_qa(&state, &question, contents, claims.account_id).await {
+        Ok(created) => json_response(
+            StatusCode::CREATED,
+            serde_json::to_string_pretty(&created).unwrap(),
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(error.status(), error)
+        }
+    }
+}
+
+/// Function to add a question and its answers in one transaction, pointing each answer at the new question
+async fn add_qa(
+    state: &AppState,
+    question: &Question,
+    contents: Vec<String>,
+    author_id: Option<i32>,
+) -> Result<QaCreated, ApiError> {
+    let mut tx = state.begin().await?;
+    let question_id = tx.add_question(question, author_id).await?;
+    let mut answer_ids = Vec::with_capacity(contents.len());
+    for content in contents {
+        let answer = Answer {
+            id: None,
+            content,
+            content_html: None,
+            question_id: question_id.clone(),
+            parent_answer_id: None,
+            created_at: None,
+            is_accepted: false,
+            author_name: None,
+        };
+        let answer = tx.add_answer(&answer, author_id).await?;
+        answer_ids.extend(answer.id);
+    }
+    tx.commit().await?;
+    Ok(QaCreated {
+        question_id,
+        answer_ids,
+    })
+}
+
+/// Function to accept an answer as the one that solved its question
+///
+/// Only the account that asked the question or an admin may accept, accepting another answer replaces the earlier one
+#[instrument]
+#[utoipa::path(post, path = "/answers/:id/accept", tag = "Answer", responses((
+    status = 200,
+    description = "Answer accepted",
+    body = None
+),
+(status = 401, description = "Login required", body = AuthError, example = json!({"status": 401, "error": "Missing credentials"})),
+(status = 403, description = "Caller did not ask the question", body = ApiError, example = json!({"status": 403, "error": "Only the question's author can accept an answer"})),
+(status = 404, description = "Answer not found", body = ApiError, example = json!({"status": 404, "error": "Answer not found"})),
+(status = 500, description = "Failed to accept the answer", body = ApiError, example = json!({"status": 500, "error": "Database error: connection refused"}))))]
+pub async fn post_accept_answer(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    match accept_answer_checked(&state, &claims, AnswerId(id)).await {
+        Ok(()) => text_response(StatusCode::OK, "Answer accepted"),
+        Err(error) => {
+            if let ApiError::DatabaseError(_) = error {
+                tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            }
+            json_error(error.status(), error)
+        }
+    }
+}
+
+/// Function to check who is accepting an answer and accept it in one transaction
+async fn accept_answer_checked(
+    state: &AppState,
+    claims: &Claims,
+    answer_id: AnswerId,
+) -> Result<(), ApiError> {
+    let mut tx = state.begin().await?;
+    let question_id = tx
+        .get_answer_question(&answer_id)
+        .await?
+        .ok_or(ApiError::AnswerNotFound)?;
+    if !tx.lock_question(&question_id).await? {
+        return Err(ApiError::QuestionNotFound);
+    }
+    if !claims.is_admin {
+        // Anonymous questions have no author, so only an admin can accept their answers
+        let author_id = tx.question_author(&question_id).await?;
+        if author_id.is_none() || author_id != claims.account_id {
+            return Err(ApiError::NotQuestionAuthor);
+        }
+    }
+    tx.accept_answer(&question_id, &answer_id, claims.account_id)
+        .await?;
+    tx.commit().await?;
+    Ok(())
+}
+
+/// Function to vote on an answer as the logged in user
+///
+/// A user only ever has one vote per answer, voting again replaces the earlier vote
+#[instrument]
+#[utoipa::path(post, path = "/answers/:id/vote", tag = "Answer", request_body = Vote, responses((
+    status = 200,
+    description = "Vote recorded, returns the answer's new score",
+    body = AnswerScore
+),
+(status = 400, description = "Vote is not 1 or -1", body = ApiError, example = json!({"status": 400, "error": "Vote must be 1 or -1"})),
+(status = 401, description = "Login required", body = ApiError, example = json!({"status": 401, "error": "Login required"})),
+(status = 404, description = "Answer not found", body = ApiError, example = json!({"status": 404, "error": "Answer not found"})),
+(status = 500, description = "Failed to record vote", body = ApiError, example = json!({"status": 500, "error": "Database error: connection refused"}))))]
+pub async fn post_answer_vote(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(id): Path<i32>,
+    Json(Vote { value }): Json<Vote>,
+) -> impl IntoResponse {
+    let account_id = match claims.account_id {
+        Some(account_id) => account_id,
+        None => {
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                ApiError::LoginRequired.to_string(),
+            );
+        }
+    };
+    if value != 1 && value != -1 {
+        return json_error(StatusCode::BAD_REQUEST, ApiError::InvalidVote.to_string());
+    }
+    let answer_id = AnswerId(id);
+    match state.get_answer_question(&answer_id).await {
+        Ok(Some(_)) => (),
+        Ok(None) => {
+            return json_error(StatusCode::NOT_FOUND, ApiError::AnswerNotFound.to_string());
+        }
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiError::DatabaseError(error.to_string()).to_string(),
+            );
+        }
+    }
+    match state.vote_answer(&answer_id, account_id, value).await {
+        Ok(score) => json_response(
+            StatusCode::OK,
+            serde_json::to_string_pretty(&AnswerScore { answer_id, score }).unwrap(),
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiError::DatabaseError(error.to_string()).to_string(),
+            )
+        }
+    }
+}
+
+/// Function to report an answer to the admins, e.g. as spam or abusive
+#[instrument]
+#[utoipa::path(post, path = "/answers/:id/report", tag = "Answer", request_body = NewReport, responses((
+    status = 201,
+    description = "Report filed, returns the report",
+    body = Report
+),
+(status = 400, description = "Reason is blank or too long", body = ApiError, example = json!({"status": 400, "error": "Invalid report: a reason is required"})),
+(status = 401, description = "Login required", body = ApiError, example = json!({"status": 401, "error": "Login required"})),
+(status = 404, description = "Answer not found", body = ApiError, example = json!({"status": 404, "error": "Answer not found"})),
+(status = 500, description = "Failed to file the report", body = ApiError, example = json!({"status": 500, "error": "Database error: connection refused"}))))]
+pub async fn post_answer_report(
+    State(state): State<AppState>,
+    claims: Claims,
+    Path(id): Path<i32>,
+    Json(NewReport { reason }): Json<NewReport>,
+) -> impl IntoResponse {
+    let account_id = match claims.account_id {
+        Some(account_id) => account_id,
+        None => {
+            return json_error(
+                StatusCode::UNAUTHORIZED,
+                ApiError::LoginRequired.to_string(),
+            );
+        }
+    };
+    let reason = reason.trim();
+    if reason.is_empty() {
+        let error = ApiError::InvalidReport("a reason is required".to_string());
+        return json_error(error.status(), error);
+    }
+    if reason.chars().count() > MAX_REPORT_REASON_LENGTH {
+        let error = ApiError::InvalidReport(format!(
+            "reason must be at most {} characters",
+            MAX_REPORT_REASON_LENGTH
+        ));
+        return json_error(error.status(), error);
+    }
+    let answer_id = AnswerId(id);
+    match state.get_answer_question(&answer_id).await {
+        Ok(Some(_)) => (),
+        Ok(None) => {
+            return json_error(StatusCode::NOT_FOUND, ApiError::AnswerNotFound.to_string());
+        }
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiError::DatabaseError(error.to_string()).to_string(),
+            );
+        }
+    }
+    match state
+        .add_answer_report(&answer_id, account_id, reason)
+        .await
+    {
+        Ok(report) => json_response(
+            StatusCode::CREATED,
+            serde_json::to_string_pretty(&report).unwrap(),
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiError::DatabaseError(error.to_string()).to_string(),
+            )
+        }
+    }
+}
+
+/// Function to list the reports filed against questions and answers, newest first
+#[instrument]
+#[utoipa::path(get, path = "/admin/reports", tag = "Admin", responses((
+    status = 200,
+    description = "Returns the reports, each with its kind, question or answer",
+    body = [Report]
+),
+(status = 403, description = "Caller is not an admin", body = AuthError, example = json!({"status": 403, "error": "Admin required"})),
+(status = 500, description = "Failed to read the reports", body = ApiError, example = json!({"status": 500, "error": "Database error: connection refused"}))))]
+pub async fn get_reports(State(state): State<AppState>, _admin: AdminClaims) -> impl IntoResponse {
+    match state.get_reports().await {
+        Ok(reports) => json_response(
+            StatusCode::OK,
+            serde_json::to_string_pretty(&reports).unwrap(),
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiError::DatabaseError(error.to_string()).to_string(),
+            )
+        }
+    }
+}
+
+/// An event page struct
+///
+/// This struct is used to page through the events log, optionally only the events of one entity type
+/// ##Example:
+/// ```
+/// {
+///  "entity_type": "question",
+///  "limit": "20",
+///  "offset": "40"
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct EventPagination {
+    entity_type: Option<String>,
+    limit: Option<i64>,
+    offset: Option<i64>,
+}
+
+/// Function to page through the log of question and answer writes, newest first
+#[instrument]
+#[utoipa::path(get, path = "/admin/events", tag = "Admin", responses((
+    status = 200,
+    description = "Returns a page of events",
+    body = [Event]
+),
+(status = 403, description = "Caller is not an admin", body = AuthError, example = json!({"status": 403, "error": "Admin required"})),
+(status = 500, description = "Failed to read the events", body = ApiError, example = json!({"status": 500, "error": "Database error: connection refused"}))))]
+pub async fn get_events(
+    State(state): State<AppState>,
+    _admin: AdminClaims,
+    Query(EventPagination {
+        entity_type,
+        limit,
+        offset,
+    }): Query<EventPagination>,
+) -> impl IntoResponse {
+    let (limit, clamped) = state.2.page_size(limit);
+    let offset = offset.unwrap_or(0).max(0);
+    match state
+        .get_events(entity_type.as_deref(), limit, offset)
+        .await
+    {
+        Ok(events) => page_response(
+            serde_json::to_string_pretty(&events).unwrap(),
+            limit,
+            clamped,
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiError::DatabaseError(error.to_string()).to_string(),
+            )
+        }
+    }
+}
+
+/// Function to list the database migrations that have been applied, to check a deploy migrated correctly
+#[instrument]
+#[utoipa::path(get, path = "/admin/migrations", tag = "Admin", responses((
+    status = 200,
+    description = "Returns the applied migrations, oldest first",
+    body = [AppliedMigration]
+),
+(status = 403, description = "Caller is not an admin", body = AuthError, example = json!({"status": 403, "error": "Admin required"})),
+(status = 500, description = "Failed to read the migrations", body = ApiError, example = json!({"status": 500, "error": "Database error: connection refused"}))))]
+pub async fn get_applied_migrations(
+    State(state): State<AppState>,
+    _admin: AdminClaims,
+) -> impl IntoResponse {
+    match state.applied_migrations().await {
+        Ok(migrations) => json_response(
+            StatusCode::OK,
+            serde_json::to_string_pretty(&migrations).unwrap(),
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(
+                StatusCode::INTERNAL_SERVER_ERROR,
+                ApiError::DatabaseError(error.to_string()).to_string(),
+            )
+        }
+    }
+}
+
+/// A maintenance struct, whether the service is in maintenance mode
+///
+/// While it is, every request but an admin's is answered with a 503
+/// ##Example:
+/// ```
+/// {
+///  "enabled": true
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Maintenance {
+    #[schema(example = true)]
+    pub enabled: bool,
+}
+
+/// Function to check whether the service is in maintenance mode
+#[instrument]
+#[utoipa::path(get, path = "/admin/maintenance", tag = "Admin", responses((
+    status = 200,
+    description = "Returns whether maintenance mode is on",
+    body = Maintenance
+),
+(status = 403, description = "Caller is not an admin", body = AuthError, example = json!({"status": 403, "error": "Admin required"}))))]
+pub async fn get_maintenance(
+    State(state): State<AppState>,
+    _admin: AdminClaims,
+) -> impl IntoResponse {
+    let maintenance = Maintenance {
+        enabled: state.4.load(Ordering::Relaxed),
     };
-    match state.add_answer(answer).await {
-        Ok(_) => Response::builder()
-            .status(StatusCode::OK)
-            .body("Answer added".to_string())
-            .unwrap(),
-        Err(error) => Response::builder()
-            .status(StatusCode::INTERNAL_SERVER_ERROR)
-            .body(error.to_string())
-            .unwrap(),
+    json_response(
+        StatusCode::OK,
+        serde_json::to_string_pretty(&maintenance).unwrap(),
+    )
+}
+
+/// Function to turn maintenance mode on or off, for deploys and migrations
+#[instrument]
+#[utoipa::path(post, path = "/admin/maintenance", tag = "Admin", request_body = Maintenance, responses((
+    status = 200,
+    description = "Returns the new maintenance mode",
+    body = Maintenance
+),
+(status = 403, description = "Caller is not an admin", body = AuthError, example = json!({"status": 403, "error": "Admin required"}))))]
+pub async fn post_maintenance(
+    State(state): State<AppState>,
+    AdminClaims(claims): AdminClaims,
+    Json(maintenance): Json<Maintenance>,
+) -> impl IntoResponse {
+    state.4.store(maintenance.enabled, Ordering::Relaxed);
+    info!(
+        "maintenance mode turned {} by {}",
+        if maintenance.enabled { "on" } else { "off" },
+        claims.email
+    );
+    json_response(
+        StatusCode::OK,
+        serde_json::to_string_pretty(&maintenance).unwrap(),
+    )
+}
+
+/// How many tags the tag list returns when no limit is asked for
+pub const DEFAULT_TAG_LIMIT: i64 = 50;
+
+/// A parameter struct for the tag list
+///
+/// ##Example:
+/// ```
+/// {
+///  "prefix": "ru",
+///  "sort": "alpha",
+///  "limit": "10",
+///  "offset": "0"
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize)]
+pub struct TagParams {
+    pub prefix: Option<String>,
+    pub sort: Option<TagSort>,
+    pub limit: Option<i64>,
+    pub offset: Option<i64>,
+}
+
+/// Function to list the tags in use with their counts, for autocomplete and a tag index
+///
+/// Sorted by frequency unless `sort=alpha`, and bounded by `limit` (default 50, at most the max page size)
+#[instrument]
+#[utoipa::path(get, path = "/tags", tag = "Question", responses((
+    status = 200,
+    description = "Returns a page of tags with how many questions carry each",
+    body = [TagCount]
+)))]
+pub async fn get_tags(
+    State(state): State<AppState>,
+    Query(TagParams {
+        prefix,
+        sort,
+        limit,
+        offset,
+    }): Query<TagParams>,
+) -> impl IntoResponse {
+    let (limit, clamped) = state.2.page_size(Some(limit.unwrap_or(DEFAULT_TAG_LIMIT)));
+    let offset = offset.unwrap_or(0).max(0);
+    let prefix = prefix
+        .as_deref()
+        .map(str::trim)
+        .filter(|prefix| !prefix.is_empty());
+    match state
+        .tag_counts(prefix, sort.unwrap_or_default(), limit, offset)
+        .await
+    {
+        Ok(tags) => page_response(serde_json::to_string_pretty(&tags).unwrap(), limit, clamped),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, error)
+        }
+    }
+}
+
+/// A tag rename struct, the tag to rename on every question and its new name
+///
+/// ##Example:
+/// ```
+/// {
+///  "from": "js",
+///  "to": "javascript"
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TagRename {
+    #[schema(example = "js")]
+    pub from: String,
+    #[schema(example = "javascript")]
+    pub to: String,
+}
+
+/// A tag rename result struct, how many questions had the tag renamed
+///
+/// ##Example:
+/// ```
+/// {
+///  "questions": 3
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct TagRenameResult {
+    #[schema(example = 3)]
+    pub questions: u64,
+}
+
+/// Function to rename a tag across all questions, e.g. "js" to "javascript"
+#[instrument]
+#[utoipa::path(post, path = "/tags/rename", tag = "Admin", request_body = TagRename, responses((
+    status = 200,
+    description = "Returns how many questions had the tag renamed",
+    body = TagRenameResult
+),
+(status = 400, description = "Invalid tag", body = ApiError, example = json!({"status": 400, "error": "Invalid tags: tag \"javascript\" is longer than 30 characters"})),
+(status = 403, description = "Caller is not an admin", body = AuthError, example = json!({"status": 403, "error": "Admin required"}))))]
+pub async fn post_rename_tag(
+    State(state): State<AppState>,
+    AdminClaims(admin): AdminClaims,
+    Json(rename): Json<TagRename>,
+) -> impl IntoResponse {
+    let (from, to) = (rename.from.trim(), rename.to.trim());
+    if from.is_empty() || to.is_empty() {
+        return json_error(StatusCode::BAD_REQUEST, ApiError::MissingParameters);
+    }
+    if let Err(error) = question::validate_tags(&Some(HashSet::from([to.to_string()]))) {
+        return json_error(StatusCode::BAD_REQUEST, error);
+    }
+    match state.rename_tag(from, to, admin.account_id).await {
+        Ok(questions) => json_response(
+            StatusCode::OK,
+            serde_json::to_string_pretty(&TagRenameResult { questions }).unwrap(),
+        ),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            json_error(StatusCode::INTERNAL_SERVER_ERROR, error)
+        }
     }
 }
 
@@ -518,7 +2719,10 @@ pub async fn post_answer(
 /// ApiError::MissingParameters // When a required parameter is missing
 /// ApiError::QuestionNotFound // When a question is not found
 /// ```
+///
+/// Error responses carry the standard error envelope as their body, see [`json_error`]
 #[derive(Debug, ToSchema, thiserror::Error)]
+#[schema(example = json!({"status": 404, "error": "Question not found"}))]
 pub enum ApiError {
     #[error("Missing parameter")]
     MissingParameters,
@@ -530,12 +2734,54 @@ pub enum ApiError {
     AccountNotFound,
     #[error("Answer not found")]
     AnswerNotFound,
+    #[error("Answer limit reached")]
+    AnswerLimitReached,
+    #[error("The question is closed")]
+    QuestionClosed,
+    #[error("Edit window expired")]
+    EditWindowExpired,
+    #[error("An account with this email already exists")]
+    EmailTaken,
+    #[error("Parent answer belongs to another question")]
+    ParentAnswerMismatch,
+    #[error("The question already has this answer")]
+    DuplicateAnswer,
+    #[error("Invalid tags: {0}")]
+    InvalidTags(String),
+    #[error("Login required")]
+    LoginRequired,
+    #[error("Too many anonymous questions, log in or try again later")]
+    AnonymousRateLimited,
+    #[error("Too many requests, try again later")]
+    RateLimited,
+    #[error("Only the question's author can accept an answer")]
+    NotQuestionAuthor,
+    #[error("Only the account itself or an admin can change it")]
+    NotAccountOwner,
+    #[error("Only the answer's author can edit it")]
+    NotAnswerAuthor,
+    #[error("Vote must be 1 or -1")]
+    InvalidVote,
+    #[error("Invalid account: {0}")]
+    InvalidAccount(String),
+    #[error("Invalid account id")]
+    InvalidAccountId,
+    #[error("Invalid duplicate: {0}")]
+    InvalidDuplicate(String),
+    #[error("Invalid question ids: {0}")]
+    InvalidQuestionIds(String),
+    #[error("Invalid report: {0}")]
+    InvalidReport(String),
+    #[error("Content too profane")]
+    TooProfane,
     #[error("Reqwest API error: {0}")]
     ReqwestAPIError(#[from] reqwest::Error),
     #[error("Middleware Reqwest API error: {0}")]
     MiddlewareReqwestAPIError(#[from] reqwest_middleware::Error),
     #[error("Client error: {0}")]
     ClientError(reqwest::Error),
+    #[error("Invalid profanity API response: {0}")]
+    InvalidProfanityResponse(String),
 }
 
 /// Implementing the IntoResponse trait for the ApiError enum
@@ -548,39 +2794,42 @@ pub enum ApiError {
 /// ```
 impl IntoResponse for ApiError {
     fn into_response(self) -> Response {
+        json_error(self.status(), &self).into_response()
+    }
+}
+
+impl ApiError {
+    /// Function to get the status code an error is answered with
+    pub fn status(&self) -> StatusCode {
         match self {
-            ApiError::MissingParameters => Response::builder()
-                .status(StatusCode::BAD_REQUEST)
-                .body("Missing parameter".to_string().into())
-                .unwrap(),
-            ApiError::QuestionNotFound => Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body("Question not found".to_string().into())
-                .unwrap(),
-            ApiError::DatabaseError(error) => Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(error.to_string().into())
-                .unwrap(),
-            ApiError::AccountNotFound => Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body("Account not found".to_string().into())
-                .unwrap(),
-            ApiError::AnswerNotFound => Response::builder()
-                .status(StatusCode::NOT_FOUND)
-                .body("Answer not found".to_string().into())
-                .unwrap(),
-            ApiError::ReqwestAPIError(error) => Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(error.to_string().into())
-                .unwrap(),
-            ApiError::MiddlewareReqwestAPIError(error) => Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(error.to_string().into())
-                .unwrap(),
-            ApiError::ClientError(error) => Response::builder()
-                .status(StatusCode::INTERNAL_SERVER_ERROR)
-                .body(error.to_string().into())
-                .unwrap(),
+            ApiError::MissingParameters
+            | ApiError::InvalidTags(_)
+            | ApiError::InvalidVote
+            | ApiError::InvalidAccount(_)
+            | ApiError::InvalidAccountId
+            | ApiError::InvalidQuestionIds(_)
+            | ApiError::InvalidDuplicate(_)
+            | ApiError::InvalidReport(_)
+            | ApiError::TooProfane
+            | ApiError::ParentAnswerMismatch => StatusCode::BAD_REQUEST,
+            ApiError::LoginRequired => StatusCode::UNAUTHORIZED,
+            ApiError::NotQuestionAuthor
+            | ApiError::NotAccountOwner
+            | ApiError::NotAnswerAuthor
+            | ApiError::EditWindowExpired => StatusCode::FORBIDDEN,
+            ApiError::AnonymousRateLimited | ApiError::RateLimited => StatusCode::TOO_MANY_REQUESTS,
+            ApiError::QuestionNotFound | ApiError::AccountNotFound | ApiError::AnswerNotFound => {
+                StatusCode::NOT_FOUND
+            }
+            ApiError::AnswerLimitReached
+            | ApiError::DuplicateAnswer
+            | ApiError::QuestionClosed
+            | ApiError::EmailTaken => StatusCode::CONFLICT,
+            ApiError::DatabaseError(_)
+            | ApiError::ReqwestAPIError(_)
+            | ApiError::MiddlewareReqwestAPIError(_)
+            | ApiError::ClientError(_)
+            | ApiError::InvalidProfanityResponse(_) => StatusCode::INTERNAL_SERVER_ERROR,
         }
     }
 }
@@ -590,3 +2839,83 @@ impl From<sqlx::Error> for ApiError {
         ApiError::DatabaseError(e.to_string())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_error_wraps_the_message_in_the_error_envelope() {
+        let response = json_error(StatusCode::NOT_FOUND, ApiError::QuestionNotFound);
+        assert_eq!(response.status(), StatusCode::NOT_FOUND);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+        let body: serde_json::Value = serde_json::from_str(response.body()).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({"status": 404, "error": "Question not found"})
+        );
+    }
+
+    #[test]
+    fn json_response_sets_the_status_and_content_type() {
+        let response = json_response(StatusCode::CREATED, "{}".to_string());
+        assert_eq!(response.status(), StatusCode::CREATED);
+        assert_eq!(response.headers()[CONTENT_TYPE], "application/json");
+        assert_eq!(response.body(), "{}");
+    }
+
+    #[test]
+    fn page_response_warns_only_when_the_limit_was_clamped() {
+        let clamped = page_response("[]".to_string(), 100, true);
+        assert_eq!(clamped.status(), StatusCode::OK);
+        assert_eq!(clamped.headers()[WARNING], "299 - \"limit clamped to 100\"");
+        let unclamped = page_response("[]".to_string(), 20, false);
+        assert!(unclamped.headers().get(WARNING).is_none());
+    }
+
+    /// Function to get the pagination links of /questions?tag=rust at `offset` of `total` questions, 10 a page
+    fn links_at(offset: i64, total: i64) -> String {
+        let uri: Uri = "/questions?tag=rust&limit=10&offset=5".parse().unwrap();
+        pagination_links(&uri, 10, offset, total)
+    }
+
+    #[test]
+    fn pagination_links_on_the_first_page() {
+        assert_eq!(
+            links_at(0, 35),
+            "</questions?tag=rust&limit=10&offset=0>; rel=\"first\", \
+             </questions?tag=rust&limit=10&offset=10>; rel=\"next\", \
+             </questions?tag=rust&limit=10&offset=30>; rel=\"last\""
+        );
+    }
+
+    #[test]
+    fn pagination_links_on_a_middle_page() {
+        assert_eq!(
+            links_at(10, 35),
+            "</questions?tag=rust&limit=10&offset=0>; rel=\"first\", \
+             </questions?tag=rust&limit=10&offset=0>; rel=\"prev\", \
+             </questions?tag=rust&limit=10&offset=20>; rel=\"next\", \
+             </questions?tag=rust&limit=10&offset=30>; rel=\"last\""
+        );
+    }
+
+    #[test]
+    fn pagination_links_on_the_last_page() {
+        assert_eq!(
+            links_at(30, 35),
+            "</questions?tag=rust&limit=10&offset=0>; rel=\"first\", \
+             </questions?tag=rust&limit=10&offset=20>; rel=\"prev\", \
+             </questions?tag=rust&limit=10&offset=30>; rel=\"last\""
+        );
+    }
+
+    #[test]
+    fn pagination_links_without_any_questions() {
+        assert_eq!(
+            links_at(0, 0),
+            "</questions?tag=rust&limit=10&offset=0>; rel=\"first\", \
+             </questions?tag=rust&limit=10&offset=0>; rel=\"last\""
+        );
+    }
+}
@@ -9,13 +9,26 @@ use axum_extra::{
     headers::{authorization::Bearer, Authorization},
     TypedHeader,
 };
-use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
+use chrono::Utc;
+use jsonwebtoken::{
+    decode, encode, errors::ErrorKind, Algorithm, DecodingKey, EncodingKey, Header, TokenData,
+    Validation,
+};
+
+/// How long a token handed out by the login endpoint stays valid
+const TOKEN_LIFETIME_HOURS: i64 = 24;
 
 /// Struct to hold the JWT keys
+///
+/// New tokens are signed with the current key only, while tokens are accepted if they were signed
+/// with the current key or any of the previous ones, so a secret can be rotated without logging everyone out.
+/// All keys are used with the same algorithm, HS256 unless told otherwise
 #[derive(Clone)]
 pub struct JwtKeys {
     encoding: EncodingKey,
-    decoding: DecodingKey,
+    /// The current key first, then the previous ones
+    decoding: Vec<DecodingKey>,
+    algorithm: Algorithm,
 }
 
 /// Implement Debug for JwtKeys
@@ -28,20 +41,109 @@ impl fmt::Debug for JwtKeys {
 /// Implement new for JwtKeys
 impl JwtKeys {
     pub fn new(secret: &[u8]) -> Self {
+        Self::with_previous(secret, &[])
+    }
+
+    /// Function to create the keys for a current secret, still accepting tokens signed with the previous secrets
+    pub fn with_previous(secret: &[u8], previous: &[&[u8]]) -> Self {
         Self {
             encoding: EncodingKey::from_secret(secret),
-            decoding: DecodingKey::from_secret(secret),
+            decoding: std::iter::once(secret)
+                .chain(previous.iter().copied())
+                .map(DecodingKey::from_secret)
+                .collect(),
+            algorithm: Algorithm::HS256,
+        }
+    }
+
+    /// Function to create the keys for an RSA key pair given as PEM, signing with RS256
+    pub fn rsa(private_pem: &[u8], public_pem: &[u8]) -> Result<Self, jsonwebtoken::errors::Error> {
+        Ok(Self {
+            encoding: EncodingKey::from_rsa_pem(private_pem)?,
+            decoding: vec![DecodingKey::from_rsa_pem(public_pem)?],
+            algorithm: Algorithm::RS256,
+        })
+    }
+
+    /// Function to sign with another HMAC algorithm than HS256
+    pub fn with_algorithm(self, algorithm: Algorithm) -> Self {
+        Self { algorithm, ..self }
+    }
+
+    /// Function to get the header new tokens are signed with
+    pub fn header(&self) -> Header {
+        Header::new(self.algorithm)
+    }
+
+    /// Function to get the validation tokens are checked with, only accepting the configured algorithm
+    pub fn validation(&self) -> Validation {
+        Validation::new(self.algorithm)
+    }
+
+    /// Function to decode a token, trying the current key first and then the previous ones
+    pub fn decode<T: serde::de::DeserializeOwned>(
+        &self,
+        token: &str,
+        validation: &Validation,
+    ) -> Result<TokenData<T>, jsonwebtoken::errors::Error> {
+        let mut result = Err(ErrorKind::InvalidSignature.into());
+        for key in &self.decoding {
+            result = decode::<T>(token, key, validation);
+            if result.is_ok() {
+                break;
+            }
         }
+        result
     }
 }
 
 /// Function to create the JWT keys
+///
+/// JWT_ALGORITHM picks the algorithm, HS256 (the default), HS384, HS512 or RS256.
+/// For RS256 the key pair is read from the PEM files JWT_PRIVATE_KEYFILE and JWT_PUBLIC_KEYFILE.
+/// Otherwise JWT_SECRETFILE is a comma separated list of secret files: the first holds the current secret,
+/// any others hold previous secrets whose tokens should keep working while a rotation is under way
 pub async fn make_jwt_keys() -> Result<JwtKeys, Box<dyn Error>> {
     use std::env::var;
 
-    let secretf = var("JWT_SECRETFILE")?;
-    let secret = tokio::fs::read_to_string(secretf).await?;
-    Ok(JwtKeys::new(secret.trim().as_bytes()))
+    let algorithm = match var("JWT_ALGORITHM") {
+        Ok(algorithm) => algorithm.trim().parse::<Algorithm>()?,
+        Err(_) => Algorithm::HS256,
+    };
+    match algorithm {
+        Algorithm::HS256 | Algorithm::HS384 | Algorithm::HS512 => (),
+        Algorithm::RS256 => {
+            let private_pem = read_key_file("JWT_PRIVATE_KEYFILE").await?;
+            let public_pem = read_key_file("JWT_PUBLIC_KEYFILE").await?;
+            return Ok(JwtKeys::rsa(&private_pem, &public_pem)?);
+        }
+        other => return Err(format!("unsupported JWT_ALGORITHM {:?}", other).into()),
+    }
+
+    let secretfs = var("JWT_SECRETFILE").map_err(|_| "JWT_SECRETFILE not set")?;
+    let mut secrets = Vec::new();
+    for secretf in secretfs.split(',').map(str::trim).filter(|f| !f.is_empty()) {
+        let secret = tokio::fs::read_to_string(secretf)
+            .await
+            .map_err(|error| format!("JWT_SECRETFILE unreadable: {}: {}", secretf, error))?;
+        secrets.push(secret.trim().to_string());
+    }
+    let (current, previous) = secrets
+        .split_first()
+        .ok_or("JWT_SECRETFILE does not name any secret file")?;
+    let previous = previous
+        .iter()
+        .map(|secret| secret.as_bytes())
+        .collect::<Vec<_>>();
+    Ok(JwtKeys::with_previous(current.as_bytes(), &previous).with_algorithm(algorithm))
+}
+
+/// Function to read the PEM file named by an environment variable, saying which variable and path failed if it can not
+async fn read_key_file(name: &str) -> Result<Vec<u8>, String> {
+    let path = std::env::var(name).map_err(|_| format!("{} not set", name))?;
+    tokio::fs::read(path.trim())
+        .await
+        .map_err(|error| format!("{} unreadable: {}: {}", name, path.trim(), error))
 }
 
 /// Error types for the auth module
@@ -55,13 +157,23 @@ pub enum AuthError {
     TokenCreation,
     #[error("invalid token")]
     InvalidToken,
+    #[error("admin required")]
+    Forbidden,
+    #[error("database error")]
+    DatabaseError,
 }
 
 /// Claims for the JWT token
 #[derive(Debug, Serialize, Deserialize)]
 pub struct Claims {
     full_name: String,
-    email: String,
+    pub email: String,
+    /// Id of the account linked to the credentials when it was registered, if there is one
+    pub account_id: Option<i32>,
+    /// Whether that account is an admin
+    #[serde(default)]
+    pub is_admin: bool,
+    exp: usize,
 }
 
 /// Body of the response for the login endpoint
@@ -89,15 +201,19 @@ pub struct AuthPayload {
 }
 
 /// Login endpoint
+///
+/// Also routed as POST, since browsers can't send a body with a GET request
 #[utoipa::path(
-    get,
+    post,
     path = "/login",
+    tag = "Account",
     responses(
         (status = 200, description = "login ok", body = AuthBody),
         (status = 400, description = "missing credentials", body = AuthError),
         (status = 401, description = "wrong credentials", body = AuthError),
-        (status = 400, description = "invalid token", body = AuthError),
+        (status = 401, description = "invalid or expired token", body = AuthError),
         (status = 500, description = "token creation error", body = AuthError),
+        (status = 500, description = "database error", body = AuthError),
     )
 )]
 pub async fn login(State(state): State<AppState>, Json(payload): Json<AuthPayload>) -> Response {
@@ -111,6 +227,7 @@ pub async fn login(State(state): State<AppState>, Json(payload): Json<AuthPayloa
         client_secret: String,
         full_name: String,
         email: String,
+        account_id: Option<i32>,
     }
 
     let user: Result<PwUser, sqlx::Error> =
@@ -120,19 +237,42 @@ pub async fn login(State(state): State<AppState>, Json(payload): Json<AuthPayloa
             .await;
     let user = match user {
         Ok(user) => user,
-        Err(_) => return AuthError::WrongCredentials.into_response(),
+        Err(sqlx::Error::RowNotFound) => return AuthError::WrongCredentials.into_response(),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return AuthError::DatabaseError.into_response();
+        }
     };
 
     if payload.client_id != user.client_id || payload.client_secret != user.client_secret {
         return AuthError::WrongCredentials.into_response();
     }
 
+    // The account is the one linked to the credentials when it was registered, never looked up by
+    // email, since an account's email can be changed to anything
+    let account: Result<Option<(i32, bool)>, sqlx::Error> =
+        sqlx::query_as(r#"SELECT id, is_admin FROM accounts WHERE id = $1"#)
+            .bind(user.account_id)
+            .fetch_optional(&state.0)
+            .await;
+    let (account_id, is_admin) = match account {
+        Ok(Some((id, is_admin))) => (Some(id), is_admin),
+        Ok(None) => (None, false),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "{:?}", error);
+            return AuthError::DatabaseError.into_response();
+        }
+    };
+
     let claims = Claims {
         full_name: user.full_name,
         email: user.email,
+        account_id,
+        is_admin,
+        exp: (Utc::now() + chrono::Duration::hours(TOKEN_LIFETIME_HOURS)).timestamp() as usize,
     };
 
-    let token = match encode(&Header::default(), &claims, &state.1.encoding) {
+    let token = match encode(&state.1.header(), &claims, &state.1.encoding) {
         Ok(token) => token,
         Err(_) => return AuthError::TokenCreation.into_response(),
     };
@@ -142,12 +282,12 @@ pub async fn login(State(state): State<AppState>, Json(payload): Json<AuthPayloa
 
 /// Implement the FromRequestParts trait for Claims
 #[async_trait]
-impl FromRequestParts<State<AppState>> for Claims {
+impl FromRequestParts<AppState> for Claims {
     type Rejection = AuthError;
 
     async fn from_request_parts(
         parts: &mut Parts,
-        state: &State<AppState>,
+        state: &AppState,
     ) -> Result<Self, Self::Rejection> {
         // Extract the token from the authorization header
         let TypedHeader(Authorization(bearer)) = parts
@@ -155,14 +295,38 @@ impl FromRequestParts<State<AppState>> for Claims {
             .await
             .map_err(|_| AuthError::InvalidToken)?;
         // Decode the user data
-        let token_data =
-            decode::<Claims>(bearer.token(), &state.1.decoding, &Validation::default())
-                .map_err(|_| AuthError::InvalidToken)?;
+        let token_data = state
+            .1
+            .decode::<Claims>(bearer.token(), &state.1.validation())
+            .map_err(|_| AuthError::InvalidToken)?;
 
         Ok(token_data.claims)
     }
 }
 
+/// Claims of a logged in admin
+///
+/// Extracting these instead of [`Claims`] guards a route: anyone who is not an admin is rejected with 403
+#[derive(Debug)]
+pub struct AdminClaims(pub Claims);
+
+/// Implement the FromRequestParts trait for AdminClaims
+#[async_trait]
+impl FromRequestParts<AppState> for AdminClaims {
+    type Rejection = AuthError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let claims = Claims::from_request_parts(parts, state).await?;
+        if !claims.is_admin {
+            return Err(AuthError::Forbidden);
+        }
+        Ok(AdminClaims(claims))
+    }
+}
+
 /// Implement the IntoResponse trait for AuthError
 impl IntoResponse for AuthError {
     fn into_response(self) -> Response {
@@ -170,7 +334,9 @@ impl IntoResponse for AuthError {
             AuthError::WrongCredentials => (StatusCode::UNAUTHORIZED, "Wrong credentials"),
             AuthError::MissingCredentials => (StatusCode::BAD_REQUEST, "Missing credentials"),
             AuthError::TokenCreation => (StatusCode::INTERNAL_SERVER_ERROR, "Token creation error"),
-            AuthError::InvalidToken => (StatusCode::BAD_REQUEST, "Invalid token"),
+            AuthError::InvalidToken => (StatusCode::UNAUTHORIZED, "Invalid token"),
+            AuthError::Forbidden => (StatusCode::FORBIDDEN, "Admin required"),
+            AuthError::DatabaseError => (StatusCode::INTERNAL_SERVER_ERROR, "Database error"),
         };
         let body = Json(serde_json::json!({
             "status": status.as_u16(),
@@ -1,9 +1,21 @@
 use crate::*;
 use api::ApiError;
+use config::Config;
 use reqwest_middleware::ClientBuilder;
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
 use std::env::var;
+use std::sync::Mutex;
+
+/// How many of the latest profanity checks the health metric looks at
+const HEALTH_WINDOW: usize = 20;
+
+/// How many checks in a row have to fail before the API is reported as unavailable
+const FAILURES_UNAVAILABLE: usize = 3;
+
+/// Largest response body read from the bad words API, anything longer is treated as a failed check
+const MAX_RESPONSE_BYTES: usize = 1024 * 1024;
 
 /// A struct representing an API response
 #[derive(Deserialize, Serialize, Debug, Clone)]
@@ -35,9 +47,9 @@ struct BadWordsResponse {
 /// # Arguments
 /// * `content` - The content to check for profanity
 /// # Returns
-/// * `Ok(String)` - The censored content
+/// * `Ok((String, i64))` - The censored content and how many bad words it had
 /// * `Err(ApiError)` - The error that occurred
-pub async fn check_profanity(content: String) -> Result<String, ApiError> {
+pub async fn check_profanity(content: String) -> Result<(String, i64), ApiError> {
     let bad_word_api_key = match var("API_LAYER_KEY") {
         Ok(key) => key,
         Err(_) => return Err(ApiError::MissingParameters),
@@ -49,7 +61,7 @@ pub async fn check_profanity(content: String) -> Result<String, ApiError> {
         .with(RetryTransientMiddleware::new_with_policy(retry_policy))
         .build();
 
-    let res = client
+    let mut res = client
         .post("https://api.apilayer.com/bad_words?censor_character=*")
         .header("apikey", bad_word_api_key)
         .header("Content-Length", content.len().to_string())
@@ -68,8 +80,108 @@ pub async fn check_profanity(content: String) -> Result<String, ApiError> {
         }
     }
 
-    match res.json::<BadWordsResponse>().await {
-        Ok(res) => Ok(res.censored_content),
-        Err(e) => Err(ApiError::ReqwestAPIError(e)),
+    // Read in chunks so a runaway response is cut off at the cap instead of being buffered whole
+    let mut body = Vec::new();
+    while let Some(chunk) = res.chunk().await.map_err(ApiError::ReqwestAPIError)? {
+        if body.len() + chunk.len() > MAX_RESPONSE_BYTES {
+            return Err(ApiError::InvalidProfanityResponse(format!(
+                "body longer than {} bytes",
+                MAX_RESPONSE_BYTES
+            )));
+        }
+        body.extend_from_slice(&chunk);
+    }
+    match serde_json::from_slice::<BadWordsResponse>(&body) {
+        Ok(res) => Ok((res.censored_content, res.bad_words_total)),
+        Err(e) => Err(ApiError::InvalidProfanityResponse(e.to_string())),
+    }
+}
+
+/// Runs a check for profanity on the given field, if profanity checking is enabled for it in the config
+/// # Arguments
+/// * `config` - The config holding the PROFANITY_ENABLED flag, the PROFANITY_FIELDS, PROFANITY_MAX_ALLOWED and PROFANITY_FAIL_OPEN
+/// * `field` - The name of the field the content comes from, `title` or `content`
+/// * `content` - The content to check for profanity
+/// # Returns
+/// * `Ok(String)` - The censored content, or the content verbatim when the field is not checked or the check failed with PROFANITY_FAIL_OPEN on
+/// * `Err(ApiError)` - The error that occurred, `TooProfane` when there are more bad words than PROFANITY_MAX_ALLOWED
+pub async fn censor(
+    config: &Config,
+    health: &ProfanityHealth,
+    field: &str,
+    content: String,
+) -> Result<String, ApiError> {
+    if !config.censors(field) {
+        return Ok(content);
+    }
+    let result = check_profanity(content.clone()).await;
+    health.record(result.is_ok());
+    let (censored, bad_words_total) = match result {
+        Ok(checked) => checked,
+        Err(error) if config.profanity_fail_open => {
+            tracing::warn!(
+                "storing {} uncensored, profanity check failed: {}",
+                field,
+                error
+            );
+            return Ok(content);
+        }
+        Err(error) => return Err(error),
+    };
+    if matches!(config.profanity_max_allowed, Some(max) if bad_words_total > max) {
+        return Err(ApiError::TooProfane);
+    }
+    Ok(censored)
+}
+
+/// Profanity health struct
+///
+/// This struct remembers whether each of the latest `HEALTH_WINDOW` profanity checks worked,
+/// so ops can tell why content is not being censored.
+/// #Example:
+/// ```
+/// health.record(false);
+/// let status = health.status();
+/// ```
+#[derive(Debug, Default)]
+pub struct ProfanityHealth {
+    outcomes: Mutex<VecDeque<bool>>,
+}
+
+/// The profanity API's health as returned by GET /health/profanity
+///
+/// ##Example:
+/// ```
+/// {
+/// "available": false,
+/// "recent_failures": 4
+/// }
+/// ```
+#[derive(Debug, Serialize)]
+pub struct ProfanityStatus {
+    /// False once the latest `FAILURES_UNAVAILABLE` checks have all failed
+    pub available: bool,
+    /// How many of the latest `HEALTH_WINDOW` checks failed
+    pub recent_failures: usize,
+}
+
+impl ProfanityHealth {
+    /// Function to record whether a profanity check worked, forgetting the oldest one once the window is full
+    pub fn record(&self, ok: bool) {
+        let mut outcomes = self.outcomes.lock().unwrap();
+        if outcomes.len() == HEALTH_WINDOW {
+            outcomes.pop_front();
+        }
+        outcomes.push_back(ok);
+    }
+
+    /// Function to get the current health, the API counts as available until checks start failing
+    pub fn status(&self) -> ProfanityStatus {
+        let outcomes = self.outcomes.lock().unwrap();
+        let failing_in_a_row = outcomes.iter().rev().take_while(|ok| !**ok).count();
+        ProfanityStatus {
+            available: failing_in_a_row < FAILURES_UNAVAILABLE,
+            recent_failures: outcomes.iter().filter(|ok| !**ok).count(),
+        }
     }
 }
@@ -0,0 +1,225 @@
+use crate::database::AnswerSort;
+use std::env::var;
+use std::str::FromStr;
+
+/// Function to read an environment variable and parse it, falling back to a default when it is unset or invalid
+pub fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    var(name)
+        .ok()
+        .and_then(|val| val.trim().parse().ok())
+        .unwrap_or(default)
+}
+
+/// Function to read an on/off environment variable, taking true/false or 1/0, falling back to a default when it is unset or invalid
+pub fn env_flag(name: &str, default: bool) -> bool {
+    match var(name).map(|val| val.trim().to_lowercase()).as_deref() {
+        Ok("true") | Ok("1") => true,
+        Ok("false") | Ok("0") => false,
+        _ => default,
+    }
+}
+
+/// Content-Security-Policy used when CONTENT_SECURITY_POLICY is not set, only the service's own origin may serve anything
+pub const DEFAULT_CONTENT_SECURITY_POLICY: &str =
+    "default-src 'self'; object-src 'none'; frame-ancestors 'none'";
+
+/// A trailing slash policy enum
+///
+/// What happens to a request whose path ends in a slash, like `/questions/`, since routes only match without one.
+/// Strip serves it as if the slash was not there, redirect answers with a 308 to the path without it
+/// ##Example:
+/// ```
+/// "redirect"
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TrailingSlash {
+    #[default]
+    Strip,
+    Redirect,
+}
+
+impl FromStr for TrailingSlash {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "strip" => Ok(TrailingSlash::Strip),
+            "redirect" => Ok(TrailingSlash::Redirect),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Invalid trailing slash policy",
+            )),
+        }
+    }
+}
+
+/// Configuration struct
+///
+/// This struct holds the tunable settings of the service, read once from the environment at startup
+/// #Example:
+/// ```
+/// let config = Config::from_env();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// Page size used when a client does not ask for one (DEFAULT_PAGE_SIZE)
+    pub default_page_size: i64,
+    /// Largest page size a client may ask for (MAX_PAGE_SIZE)
+    pub max_page_size: i64,
+    /// Most answers a single question may have (MAX_ANSWERS_PER_QUESTION)
+    pub max_answers_per_question: i64,
+    /// How often expired sessions are purged from the database, in seconds, None when SESSION_CLEANUP_SECS is 0
+    pub session_cleanup_secs: Option<u64>,
+    /// Whether posted content is run through the bad words api (PROFANITY_ENABLED)
+    pub profanity_enabled: bool,
+    /// Which fields are run through the bad words api, out of title and content (PROFANITY_FIELDS)
+    pub profanity_fields: Vec<String>,
+    /// Most bad words a checked field may have before it is rejected instead of censored, unset censors any amount (PROFANITY_MAX_ALLOWED)
+    pub profanity_max_allowed: Option<i64>,
+    /// Whether content is stored uncensored, with a warning logged, when the bad words api can not be used (PROFANITY_FAIL_OPEN)
+    pub profanity_fail_open: bool,
+    /// Whether a structured access line is logged for every request (ACCESS_LOG=1)
+    pub access_log: bool,
+    /// Whether request and response bodies are logged, redacted and truncated (LOG_BODIES=1).
+    /// Only ever on in debug builds, a release build ignores LOG_BODIES
+    pub log_bodies: bool,
+    /// Whether questions can be posted without logging in (ALLOW_ANONYMOUS_QUESTIONS)
+    pub allow_anonymous_questions: bool,
+    /// Most questions a single IP may post anonymously in an hour (ANONYMOUS_QUESTIONS_PER_HOUR)
+    pub anonymous_questions_per_hour: usize,
+    /// How many times a single IP may check if an email has an account in a minute (ACCOUNT_EXISTS_CHECKS_PER_MINUTE)
+    pub account_exists_checks_per_minute: usize,
+    /// Title similarity, between 0 and 1, above which a new question is flagged as a likely duplicate (SIMILAR_TITLE_THRESHOLD)
+    pub similar_title_threshold: f32,
+    /// How answers are ordered when a request does not ask for an order (ANSWER_SORT_DEFAULT)
+    pub answer_sort_default: AnswerSort,
+    /// Whether deleting an account also deletes its questions and answers when the request does not say (ACCOUNT_DELETE_CASCADE)
+    pub account_delete_cascade: bool,
+    /// How long after it was posted an answer can still be edited by anyone but an admin, in seconds,
+    /// None when ANSWER_EDIT_WINDOW_SECS is unset or 0 so answers can always be edited
+    pub answer_edit_window_secs: Option<i64>,
+    /// Whether an answer with exactly the same content as another answer on its question is rejected (DEDUPE_ANSWERS)
+    pub dedupe_answers: bool,
+    /// Whether every request that would change something is turned away, reads keep working (READ_ONLY)
+    pub read_only: bool,
+    /// Content-Security-Policy header sent with responses, None when CONTENT_SECURITY_POLICY is set empty
+    pub content_security_policy: Option<String>,
+    /// HTML page shown to browsers for unknown paths instead of the built in one, read from the file NOT_FOUND_PAGE
+    pub not_found_page: Option<String>,
+    /// How long a page of the question list is cached for, in milliseconds, 0 turns the cache off (QUESTION_CACHE_MS)
+    pub question_cache_ms: u64,
+    /// Whether paths with a trailing slash are served as is or redirected to the path without it (TRAILING_SLASH)
+    pub trailing_slash: TrailingSlash,
+    /// Where the frontend is served, the RSS feed links each question to its page there (SITE_URL)
+    pub site_url: String,
+}
+
+impl Config {
+    /// Function to create the config from the environment, using the defaults for anything not set
+    pub fn from_env() -> Self {
+        Config {
+            default_page_size: env_or("DEFAULT_PAGE_SIZE", 20),
+            max_page_size: env_or("MAX_PAGE_SIZE", 100),
+            max_answers_per_question: env_or("MAX_ANSWERS_PER_QUESTION", 100),
+            session_cleanup_secs: Some(env_or("SESSION_CLEANUP_SECS", 300))
+                .filter(|secs| *secs > 0),
+            profanity_enabled: env_flag("PROFANITY_ENABLED", true),
+            profanity_fields: env_or("PROFANITY_FIELDS", "title,content".to_string())
+                .split(',')
+                .map(|field| field.trim().to_lowercase())
+                .filter(|field| !field.is_empty())
+                .collect(),
+            // A typo here would quietly turn rejecting off, so it stops the startup instead
+            profanity_max_allowed: var("PROFANITY_MAX_ALLOWED")
+                .ok()
+                .filter(|max| !max.trim().is_empty())
+                .map(|max| {
+                    max.trim()
+                        .parse()
+                        .expect("PROFANITY_MAX_ALLOWED should be a valid whole number")
+                }),
+            profanity_fail_open: env_flag("PROFANITY_FAIL_OPEN", false),
+            access_log: env_flag("ACCESS_LOG", false),
+            log_bodies: cfg!(debug_assertions) && env_flag("LOG_BODIES", false),
+            allow_anonymous_questions: env_flag("ALLOW_ANONYMOUS_QUESTIONS", true),
+            anonymous_questions_per_hour: env_or("ANONYMOUS_QUESTIONS_PER_HOUR", 3),
+            account_exists_checks_per_minute: env_or("ACCOUNT_EXISTS_CHECKS_PER_MINUTE", 10),
+            similar_title_threshold: env_or("SIMILAR_TITLE_THRESHOLD", 0.6),
+            answer_sort_default: env_or("ANSWER_SORT_DEFAULT", AnswerSort::Oldest),
+            dedupe_answers: env_flag("DEDUPE_ANSWERS", false),
+            answer_edit_window_secs: Some(env_or("ANSWER_EDIT_WINDOW_SECS", 0))
+                .filter(|secs| *secs > 0),
+            account_delete_cascade: env_flag("ACCOUNT_DELETE_CASCADE", false),
+            read_only: env_flag("READ_ONLY", false),
+            content_security_policy: Some(env_or(
+                "CONTENT_SECURITY_POLICY",
+                DEFAULT_CONTENT_SECURITY_POLICY.to_string(),
+            ))
+            .filter(|policy| !policy.is_empty()),
+            not_found_page: var("NOT_FOUND_PAGE").ok().and_then(|path| {
+                std::fs::read_to_string(path.trim())
+                    .map_err(|error| {
+                        tracing::warn!("could not read NOT_FOUND_PAGE {}: {}", path, error)
+                    })
+                    .ok()
+            }),
+            trailing_slash: env_or("TRAILING_SLASH", TrailingSlash::Strip),
+            question_cache_ms: env_or("QUESTION_CACHE_MS", 5000),
+            site_url: env_or("SITE_URL", "http://localhost:8080".to_string()),
+        }
+    }
+
+    /// Function to check if a field, title or content, should be run through the bad words api
+    pub fn censors(&self, field: &str) -> bool {
+        self.profanity_enabled && self.profanity_fields.iter().any(|f| f == field)
+    }
+
+    /// Function to resolve a requested page size against the configured bounds
+    ///
+    /// Returns the page size to use, never below 1, and whether the requested size had to be clamped to the max
+    pub fn page_size(&self, requested: Option<i64>) -> (i64, bool) {
+        let (limit, clamped) = match requested {
+            Some(limit) if limit > self.max_page_size => (self.max_page_size, true),
+            Some(limit) if limit > 0 => (limit, false),
+            _ => (self.default_page_size.min(self.max_page_size), false),
+        };
+        // A page size of 0 from DEFAULT_PAGE_SIZE or MAX_PAGE_SIZE would divide by zero in the pagination links
+        (limit.max(1), clamped)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Function to build a config with the given page sizes, the rest from the defaults
+    fn paged(default_page_size: i64, max_page_size: i64) -> Config {
+        Config {
+            default_page_size,
+            max_page_size,
+            ..Config::from_env()
+        }
+    }
+
+    #[test]
+    fn page_size_keeps_a_limit_within_bounds() {
+        assert_eq!(paged(20, 100).page_size(Some(30)), (30, false));
+    }
+
+    #[test]
+    fn page_size_clamps_a_limit_over_the_max() {
+        assert_eq!(paged(20, 100).page_size(Some(500)), (100, true));
+    }
+
+    #[test]
+    fn page_size_falls_back_to_the_default() {
+        assert_eq!(paged(20, 100).page_size(None), (20, false));
+        assert_eq!(paged(20, 100).page_size(Some(0)), (20, false));
+    }
+
+    #[test]
+    fn page_size_is_never_below_one() {
+        assert_eq!(paged(0, 100).page_size(None), (1, false));
+        assert_eq!(paged(20, 0).page_size(Some(5)), (1, true));
+    }
+}
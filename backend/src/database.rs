@@ -1,10 +1,24 @@
 use chrono::{DateTime, Utc};
 
 use crate::{
+    answer_stream::AnswerStreams,
     auth::{make_jwt_keys, JwtKeys},
+    bad_words_api::ProfanityHealth,
+    config::{env_or, Config},
+    question_cache::QuestionCache,
+    rate_limit::IpRateLimiter,
     *,
 };
+use sqlx::{
+    postgres::{PgConnectOptions, PgRow, PgSslMode},
+    Postgres, Transaction,
+};
 use std::collections::HashSet;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// An account struct to represent an account in the database
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, ToSchema, Clone)]
@@ -15,11 +29,118 @@ pub struct Account {
     pub email: String,
     #[schema(example = "someHashOfAPassword")]
     pub password: String,
+    /// Name shown as the author of the account's answers instead of its email, an empty one clears it
+    #[schema(example = "Nathan")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+}
+
+/// Shortest password an account may have
+pub const MIN_PASSWORD_LENGTH: usize = 8;
+
+/// Longest display name an account may have, in characters
+pub const MAX_DISPLAY_NAME_LENGTH: usize = 50;
+
+/// Email of the placeholder account that keeps the content of deleted accounts, added by a migration
+pub const DELETED_USER_EMAIL: &str = "deleted-user@invalid";
+
+/// Passwords that are long enough and mix letters and digits, but are still among the first tried
+const COMMON_PASSWORDS: &[&str] = &[
+    "password1",
+    "password123",
+    "passw0rd",
+    "abc12345",
+    "abcd1234",
+    "qwerty123",
+    "qwerty12",
+    "letmein1",
+    "iloveyou1",
+    "welcome1",
+    "admin123",
+    "trustno1",
+];
+
+impl Account {
+    /// Function to check an account before it is stored
+    ///
+    /// #Errors:
+    /// Returns `ApiError::InvalidAccount` naming the field at fault: an email without an `@`,
+    /// a password shorter than `MIN_PASSWORD_LENGTH`, without both a letter and a digit, or a common one
+    pub fn validate(&self) -> Result<(), crate::api::ApiError> {
+        use crate::api::ApiError::InvalidAccount;
+
+        if !self.email.contains('@') {
+            return Err(InvalidAccount("email must be an email address".to_string()));
+        }
+        let password = &self.password;
+        if password.chars().count() < MIN_PASSWORD_LENGTH {
+            return Err(InvalidAccount(format!(
+                "password must be at least {} characters",
+                MIN_PASSWORD_LENGTH
+            )));
+        }
+        if !password.chars().any(|c| c.is_alphabetic())
+            || !password.chars().any(|c| c.is_ascii_digit())
+        {
+            return Err(InvalidAccount(
+                "password must contain at least one letter and one digit".to_string(),
+            ));
+        }
+        if COMMON_PASSWORDS.contains(&password.to_lowercase().as_str()) {
+            return Err(InvalidAccount("password is too common".to_string()));
+        }
+        if let Some(display_name) = &self.display_name {
+            if display_name.chars().count() > MAX_DISPLAY_NAME_LENGTH {
+                return Err(InvalidAccount(format!(
+                    "display name must be at most {} characters",
+                    MAX_DISPLAY_NAME_LENGTH
+                )));
+            }
+        }
+        Ok(())
+    }
 }
 
 #[derive(Deserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash, sqlx::Type)]
 pub struct AccountId(pub i32);
 
+impl FromStr for AccountId {
+    type Err = std::io::Error;
+
+    /// Parses an account id, only positive ids are valid since that is all the database hands out
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.parse::<i32>() {
+            Ok(id) if id > 0 => Ok(AccountId(id)),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Invalid account id",
+            )),
+        }
+    }
+}
+
+/// A public account struct, an account without its password, safe to hand back to clients
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, ToSchema, Clone)]
+pub struct PublicAccount {
+    #[schema(example = 1, value_type = i32)]
+    pub id: AccountId,
+    #[schema(example = "moes@pdx.edu")]
+    pub email: String,
+    #[schema(example = "Nathan")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+}
+
+impl From<Account> for PublicAccount {
+    fn from(account: Account) -> Self {
+        PublicAccount {
+            id: account.id,
+            email: account.email,
+            display_name: account.display_name,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct Session {
     pub exp: DateTime<Utc>,
@@ -27,19 +148,556 @@ pub struct Session {
     pub nbf: DateTime<Utc>,
 }
 
+/// An answer id struct, serialized as the bare integer
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(transparent)]
+pub struct AnswerId(pub i32);
+
 /// An answer struct to represent an answer in the database
-#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+///
+/// Answers can reply to another answer on the same question through `parent_answer_id`.
+/// The content is Markdown, `content_html` is that content rendered and sanitized when the answer is written.
+/// `is_accepted` is read from the question's `accepted_answer_id`, it is not stored on the answer
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, ToSchema, Clone)]
 pub struct Answer {
-    #[schema(example = "This is an answer to the question")]
+    #[schema(value_type = Option<i32>, example = "1")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<AnswerId>,
+    #[schema(example = "This is an **answer** to the question")]
     pub content: String,
-    #[schema(example = "1")]
+    #[schema(example = "<p>This is an <strong>answer</strong> to the question</p>")]
+    #[serde(default, skip_deserializing)]
+    pub content_html: Option<String>,
+    #[schema(example = 1, value_type = i32)]
     pub question_id: QuestionId,
+    #[schema(value_type = Option<i32>, example = "2")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_answer_id: Option<AnswerId>,
+    #[schema(value_type = Option<String>, example = "2024-05-01T12:00:00")]
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<chrono::NaiveDateTime>,
+    /// Whether the question's author accepted this answer
+    #[serde(default, skip_deserializing)]
+    pub is_accepted: bool,
+    /// Who wrote the answer, their display name or else their masked email. Left out for anonymous answers
+    #[schema(example = "Nathan")]
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub author_name: Option<String>,
+}
+
+/// An answer body struct, the fields of an answer that can be sent to POST and PUT /answers
+///
+/// Unknown fields are rejected, including the ones an answer only has in responses such as `created_at`
+/// ##Example:
+/// ```
+/// {
+///  "content": "This is an answer to the question",
+///  "question_id": 1,
+///  "parent_answer_id": 2
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct AnswerBody {
+    /// Ignored, the answer is picked by the path or given its id by the database
+    #[schema(value_type = Option<i32>, example = "1")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<AnswerId>,
+    #[schema(example = "This is an **answer** to the question")]
+    pub content: String,
+    #[schema(example = 1, value_type = i32)]
+    pub question_id: QuestionId,
+    #[schema(value_type = Option<i32>, example = "2")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_answer_id: Option<AnswerId>,
+}
+
+impl From<AnswerBody> for Answer {
+    fn from(answer: AnswerBody) -> Self {
+        Answer {
+            id: answer.id,
+            content: answer.content,
+            content_html: None,
+            question_id: answer.question_id,
+            parent_answer_id: answer.parent_answer_id,
+            created_at: None,
+            is_accepted: false,
+            author_name: None,
+        }
+    }
+}
+
+/// An answer sort enum
+///
+/// How the answers to a question are ordered: oldest first, newest first or highest score first.
+/// When a request gives no sort, ANSWER_SORT_DEFAULT decides
+/// ##Example:
+/// ```
+/// "votes"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AnswerSort {
+    #[default]
+    Oldest,
+    Newest,
+    Votes,
+}
+
+impl FromStr for AnswerSort {
+    type Err = std::io::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "oldest" => Ok(AnswerSort::Oldest),
+            "newest" => Ok(AnswerSort::Newest),
+            "votes" => Ok(AnswerSort::Votes),
+            _ => Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                "Invalid answer sort",
+            )),
+        }
+    }
+}
+
+/// The number of answers a question has
+///
+/// ##Example:
+/// ```
+/// {
+///  "count": 3
+/// }
+/// ```
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AnswerCount {
+    #[schema(example = 3)]
+    pub count: i64,
+}
+
+/// A tag and how many questions carry it
+///
+/// ##Example:
+/// ```
+/// {
+///  "tag": "rust",
+///  "count": 12
+/// }
+/// ```
+#[derive(Debug, Serialize, ToSchema)]
+pub struct TagCount {
+    #[schema(example = "rust")]
+    pub tag: String,
+    #[schema(example = 12)]
+    pub count: i64,
+}
+
+/// The results of a site wide search, the matching questions and the matching answers
+///
+/// ##Example:
+/// ```
+/// {
+///  "questions": [{ "id": 1, "title": "What is cargo toml?", "content": "...", "category": "tooling", "pinned": false, "answer_count": 2 }],
+///  "answers": [{ "id": 4, "content": "Cargo.toml is the manifest...", "question_id": 1, "is_accepted": false }]
+/// }
+/// ```
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SearchResults {
+    pub questions: Vec<Question>,
+    pub answers: Vec<Answer>,
+}
+
+/// A vote struct, the body of a vote on an answer
+///
+/// The value is 1 for an upvote and -1 for a downvote
+/// ##Example:
+/// ```
+/// {
+///  "value": 1
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct Vote {
+    #[schema(example = 1)]
+    pub value: i16,
+}
+
+/// A report body struct, why a question or answer is being reported
+///
+/// ##Example:
+/// ```
+/// {
+///  "reason": "Spam"
+/// }
+/// ```
+#[derive(Debug, Serialize, Deserialize, ToSchema)]
+pub struct NewReport {
+    #[schema(example = "Spam")]
+    pub reason: String,
+}
+
+/// Longest reason a report may give, in characters
+pub const MAX_REPORT_REASON_LENGTH: usize = 500;
+
+/// A report kind enum, whether a report is about a question or an answer
+///
+/// ##Example:
+/// ```
+/// "answer"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum ReportKind {
+    Question,
+    Answer,
+}
+
+/// A report struct, a question or answer flagged for an admin to look at
+///
+/// Only the id matching the kind is set
+/// ##Example:
+/// ```
+/// {
+///  "id": 1,
+///  "kind": "answer",
+///  "answer_id": 3,
+///  "reporter_id": 2,
+///  "reason": "Spam",
+///  "created_at": "2024-05-01T12:00:00"
+/// }
+/// ```
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Report {
+    #[schema(example = 1)]
+    pub id: i32,
+    pub kind: ReportKind,
+    #[schema(value_type = Option<i32>)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub question_id: Option<QuestionId>,
+    #[schema(example = 3, value_type = Option<i32>)]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub answer_id: Option<AnswerId>,
+    /// The account that filed the report, None once that account is deleted
+    #[schema(example = 2)]
+    pub reporter_id: Option<i32>,
+    #[schema(example = "Spam")]
+    pub reason: String,
+    #[schema(value_type = String, example = "2024-05-01T12:00:00")]
+    pub created_at: chrono::NaiveDateTime,
+}
+
+/// A feed entry struct, the little of a question the RSS feed needs
+///
+/// `created_on` is when the question was asked, used as the entry's publish date
+#[derive(Debug, Clone)]
+pub struct FeedEntry {
+    pub id: QuestionId,
+    pub title: String,
+    pub created_on: chrono::NaiveDateTime,
+}
+
+/// Function to build a report from a row of the reports table
+fn report_from_row(row: &PgRow) -> Result<Report, sqlx::Error> {
+    let question_id: Option<i32> = row.try_get("question_id")?;
+    let answer_id: Option<i32> = row.try_get("answer_id")?;
+    Ok(Report {
+        id: row.try_get("id")?,
+        kind: if answer_id.is_some() {
+            ReportKind::Answer
+        } else {
+            ReportKind::Question
+        },
+        question_id: question_id.map(QuestionId),
+        answer_id: answer_id.map(AnswerId),
+        reporter_id: row.try_get("reporter_id")?,
+        reason: row.try_get("reason")?,
+        created_at: row.try_get("created_at")?,
+    })
+}
+
+/// An event action enum, what a write did to its entity
+///
+/// ##Example:
+/// ```
+/// "create"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, ToSchema, sqlx::Type)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum EventAction {
+    Create,
+    Update,
+    Delete,
+}
+
+/// An event struct, one row of the append only log of writes
+///
+/// The payload is the entity as written, or what was removed with it for a delete
+/// ##Example:
+/// ```
+/// {
+///  "id": 1,
+///  "entity_type": "question",
+///  "entity_id": 4,
+///  "action": "create",
+///  "actor_id": 2,
+///  "created_at": "2024-05-01T12:00:00",
+///  "payload": { "title": "What is rust?" }
+/// }
+/// ```
+#[derive(Debug, Serialize, ToSchema)]
+pub struct Event {
+    #[schema(example = 1)]
+    pub id: i64,
+    #[schema(example = "question")]
+    pub entity_type: String,
+    #[schema(example = 4)]
+    pub entity_id: i32,
+    pub action: EventAction,
+    /// The logged in account that made the change, None for anonymous writes
+    #[schema(example = 2)]
+    pub actor_id: Option<i32>,
+    #[schema(value_type = String, example = "2024-05-01T12:00:00")]
+    pub created_at: chrono::NaiveDateTime,
+    #[schema(value_type = Object)]
+    pub payload: serde_json::Value,
+}
+
+/// The total of the votes on an answer
+///
+/// ##Example:
+/// ```
+/// {
+///  "answer_id": 1,
+///  "score": 4
+/// }
+/// ```
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AnswerScore {
+    #[schema(example = 1, value_type = i32)]
+    pub answer_id: AnswerId,
+    #[schema(example = 4)]
+    pub score: i64,
+}
+
+/// A migration that has been applied to the database, as recorded by sqlx
+///
+/// ##Example:
+/// ```
+/// {
+///  "version": 1,
+///  "description": "create",
+///  "installed_on": "2024-05-01T12:00:00Z"
+/// }
+/// ```
+#[derive(Debug, Serialize, ToSchema)]
+pub struct AppliedMigration {
+    #[schema(example = 1)]
+    pub version: i64,
+    #[schema(example = "create")]
+    pub description: String,
+    #[schema(value_type = String, example = "2024-05-01T12:00:00Z")]
+    pub installed_on: DateTime<Utc>,
+}
+
+/// How many times [`AppState::with_tx`] retries a transaction that hit a serialization failure or deadlock
+pub const TX_RETRIES: u32 = 3;
+
+/// Function to check if a transaction failed with a serialization failure (40001) or deadlock (40P01),
+/// both of which are safe to retry from the start
+fn is_retryable(error: &sqlx::Error) -> bool {
+    matches!(
+        error
+            .as_database_error()
+            .and_then(|error| error.code())
+            .as_deref(),
+        Some("40001") | Some("40P01")
+    )
+}
+
+/// Function to check if an error is a unique violation (23505), e.g. an email some other account already has
+pub fn is_unique_violation(error: &(dyn Error + 'static)) -> bool {
+    error
+        .downcast_ref::<sqlx::Error>()
+        .and_then(|error| error.as_database_error())
+        .is_some_and(|error| error.is_unique_violation())
+}
+
+/// The future returned by the closure given to [`AppState::with_tx`]
+pub type TxFuture<'c, T> = Pin<Box<dyn Future<Output = Result<T, sqlx::Error>> + Send + 'c>>;
+
+/// A question bundled with all of its answers
+///
+/// `answered_by_me` is true when the logged in caller has already answered the question
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QuestionWithAnswers {
+    #[serde(flatten)]
+    pub question: Question,
+    pub answers: Vec<Answer>,
+    #[schema(example = "false")]
+    pub answered_by_me: bool,
+}
+
+/// Function to build a question from a row of the questions table
+fn question_from_row(row: &PgRow) -> Result<Question, sqlx::Error> {
+    let tags: Option<Vec<String>> = row.try_get("tags")?;
+    let duplicate_of: Option<i32> = row.try_get("duplicate_of")?;
+    Ok(Question {
+        id: QuestionId(row.try_get("id")?),
+        title: row.try_get("title")?,
+        content: row.try_get("content")?,
+        tags: tags.map(|tags| tags.into_iter().collect::<HashSet<String>>()),
+        category: row.try_get("category")?,
+        pinned: row.try_get("pinned")?,
+        answer_count: None,
+        has_accepted_answer: None,
+        duplicate_of: duplicate_of.map(QuestionId),
+        close_reason: row.try_get("close_reason")?,
+    })
+}
+
+/// Function to build a question from a row of `QUESTIONS_WITH_ANSWER_COUNT`, answer count and accepted status included
+fn question_with_count_from_row(row: &PgRow) -> Result<Question, sqlx::Error> {
+    Ok(Question {
+        answer_count: Some(row.try_get("answer_count")?),
+        has_accepted_answer: Some(row.try_get("has_accepted_answer")?),
+        ..question_from_row(row)?
+    })
+}
+
+/// Questions joined with their answer counts, counted in one grouped query rather than once per question,
+/// and whether they have an accepted answer
+const QUESTIONS_WITH_ANSWER_COUNT: &str = r#"SELECT q.*, COALESCE(counts.answer_count, 0) AS answer_count,
+    q.accepted_answer_id IS NOT NULL AS has_accepted_answer
+    FROM questions q
+    LEFT JOIN (
+        SELECT corresponding_question, COUNT(*) AS answer_count FROM answers GROUP BY corresponding_question
+    ) counts ON counts.corresponding_question = q.id"#;
+
+/// Function to get the SQL for the name an author is shown by, given the column holding their account id
+///
+/// That is their display name, or else their email with all but its first character before the @ masked
+fn author_name(author_id: &str) -> String {
+    format!(
+        r#"(SELECT COALESCE(display_name, regexp_replace(email, '^(.)[^@]*', '\1***'))
+        FROM accounts WHERE accounts.id = {}) AS author_name"#,
+        author_id
+    )
+}
+
+/// Function to get the ORDER BY clause for a page of questions, pinned questions always come first
+fn question_order_by(sort: QuestionSort) -> &'static str {
+    match sort {
+        QuestionSort::Id => "pinned DESC, id",
+        QuestionSort::Active => "pinned DESC, last_activity_at DESC, id DESC",
+    }
+}
+
+/// Function to escape text for a LIKE pattern, so `%` and `_` in it match themselves rather than any text
+fn escape_like(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('%', "\\%")
+        .replace('_', "\\_")
+}
+
+/// Function to turn a question's tags into the value stored in the tags column
+///
+/// No tags and an empty set of tags are both stored as NULL, never as an empty array
+fn tags_column(tags: &Option<HashSet<String>>) -> Option<Vec<String>> {
+    tags.as_ref()
+        .filter(|tags| !tags.is_empty())
+        .map(|tags| tags.iter().cloned().collect())
+}
+
+/// Function to build an answer from a row of the answers table
+///
+/// The query must select `is_accepted` and `author_name` columns next to the answer's own columns
+fn answer_from_row(row: &PgRow) -> Result<Answer, sqlx::Error> {
+    let parent_answer_id: Option<i32> = row.try_get("parent_answer_id")?;
+    Ok(Answer {
+        id: Some(AnswerId(row.try_get("id")?)),
+        content: row.try_get("content")?,
+        content_html: row.try_get("content_html")?,
+        question_id: QuestionId(row.try_get("corresponding_question")?),
+        parent_answer_id: parent_answer_id.map(AnswerId),
+        created_at: row.try_get("created_at")?,
+        is_accepted: row.try_get("is_accepted")?,
+        author_name: row.try_get("author_name")?,
+    })
+}
+
+/// Function to build the options to connect with from a connection url and the SSL variables
+///
+/// PG_SSLMODE (disable, require or verify-full) overrides any sslmode in the url, PG_SSLROOTCERT is the CA cert
+/// the server's certificate is checked against with verify-full
+fn connect_options(url: &str) -> Result<PgConnectOptions, Box<dyn Error>> {
+    let mut options = PgConnectOptions::from_str(url)?;
+    if let Ok(mode) = std::env::var("PG_SSLMODE") {
+        let mode = match mode.trim().to_lowercase().as_str() {
+            "disable" => PgSslMode::Disable,
+            "require" => PgSslMode::Require,
+            "verify-full" => PgSslMode::VerifyFull,
+            other => {
+                return Err(format!(
+                    "PG_SSLMODE must be disable, require or verify-full, not {}",
+                    other
+                )
+                .into())
+            }
+        };
+        options = options.ssl_mode(mode);
+    }
+    if let Ok(path) = std::env::var("PG_SSLROOTCERT") {
+        options = options.ssl_root_cert(path.trim());
+    }
+    Ok(options)
+}
+
+/// Function to connect to the database, trying again up to `retries` times with `delay` between tries
+///
+/// In a docker compose setup the service can start before Postgres takes connections, so the first
+/// tries failing is expected. Each failed try is logged, the last error is returned once all have failed
+async fn connect_with_retry(
+    options: PgConnectOptions,
+    retries: u32,
+    delay: Duration,
+) -> Result<PgPool, sqlx::Error> {
+    let mut attempt = 0;
+    loop {
+        attempt += 1;
+        match PgPool::connect_with(options.clone()).await {
+            Ok(pool) => return Ok(pool),
+            Err(error) if attempt <= retries => {
+                tracing::warn!(
+                    "database connection attempt {} of {} failed, retrying in {}ms: {}",
+                    attempt,
+                    retries + 1,
+                    delay.as_millis(),
+                    error
+                );
+                tokio::time::sleep(delay).await;
+            }
+            Err(error) => return Err(error),
+        }
+    }
 }
 
 /// Application state struct
-/// This struct is used to hold the state of the application, which is currently only the questions for the API
+/// This struct is used to hold the state of the application: the database pool, the JWT keys, the config,
+/// the channels new answers are streamed through, whether the service is in maintenance mode, how
+/// the profanity API has been doing, the rate limit on anonymous questions, the cached pages of the question list
+/// and the rate limit on checking if an email has an account
 #[derive(Clone, Debug)]
-pub struct AppState(pub PgPool, pub JwtKeys);
+pub struct AppState(
+    pub PgPool,
+    pub JwtKeys,
+    pub Arc<Config>,
+    pub Arc<AnswerStreams>,
+    pub Arc<AtomicBool>,
+    pub Arc<ProfanityHealth>,
+    pub Arc<IpRateLimiter>,
+    pub Arc<QuestionCache>,
+    pub Arc<IpRateLimiter>,
+);
 
 /// Implementing the AppState struct with basic functions to use for API and state management operations
 impl AppState {
@@ -59,211 +717,1820 @@ impl AppState {
     pub async fn new() -> Result<Self, Box<dyn Error>> {
         use std::env::var;
 
-        let port = var("PG_PORT")
-            .map(|val| val.parse().expect("PG_PORT should be a valid u16"))
-            .unwrap_or(6565);
-        let password = var("PG_PASSWORD")?;
-        let url = format!(
-            "postgres://{}:{}@{}:{}",
-            var("PG_USER")?,
-            password.trim(),
-            var("PG_HOST")?,
-            port
-        );
-        let pool = PgPool::connect(&url).await?;
+        // DATABASE_URL is what most hosting platforms hand out, it wins over the PG_* variables
+        let url = match var("DATABASE_URL") {
+            Ok(url) if !url.trim().is_empty() => url.trim().to_string(),
+            _ => {
+                let port = var("PG_PORT")
+                    .map(|val| val.parse().expect("PG_PORT should be a valid u16"))
+                    .unwrap_or(6565);
+                let password = var("PG_PASSWORD")?;
+                format!(
+                    "postgres://{}:{}@{}:{}",
+                    var("PG_USER")?,
+                    password.trim(),
+                    var("PG_HOST")?,
+                    port
+                )
+            }
+        };
+        let pool = connect_with_retry(
+            connect_options(&url)?,
+            env_or("DB_CONNECT_RETRIES", 10),
+            Duration::from_millis(env_or("DB_CONNECT_DELAY_MS", 1000)),
+        )
+        .await?;
         sqlx::migrate!().run(&pool).await?;
         let keys = make_jwt_keys().await?;
-        Ok(AppState(pool, keys))
+        // The first admin has to come from somewhere, after that admins promote other accounts
+        if let Ok(email) = var("ADMIN_EMAIL") {
+            sqlx::query(r#"UPDATE accounts SET is_admin = true WHERE email = $1;"#)
+                .bind(email.trim())
+                .execute(&pool)
+                .await?;
+        }
+        let config = Config::from_env();
+        if config.profanity_enabled && var("API_LAYER_KEY").is_err() {
+            return Err("API_LAYER_KEY must be set when PROFANITY_ENABLED is true".into());
+        }
+        let anonymous_questions = IpRateLimiter::new(
+            config.anonymous_questions_per_hour,
+            std::time::Duration::from_secs(60 * 60),
+        );
+        let question_cache = QuestionCache::new(Duration::from_millis(config.question_cache_ms));
+        let account_exists_checks = IpRateLimiter::new(
+            config.account_exists_checks_per_minute,
+            Duration::from_secs(60),
+        );
+        Ok(AppState(
+            pool,
+            keys,
+            Arc::new(config),
+            Arc::new(AnswerStreams::default()),
+            Arc::new(AtomicBool::new(false)),
+            Arc::new(ProfanityHealth::default()),
+            Arc::new(anonymous_questions),
+            Arc::new(question_cache),
+            Arc::new(account_exists_checks),
+        ))
     }
 
-    /// Function to get a question from the questions database, by id
-    pub async fn get_question(&self, id: &QuestionId) -> Result<Option<Question>, Box<dyn Error>> {
-        let row = sqlx::query(r#"SELECT * FROM questions WHERE id = $1;"#)
-            .bind(id.0)
-            .fetch_one(&self.0)
-            .await?;
+    /// Function to run statements in a single transaction
+    ///
+    /// Begins a transaction, runs the closure with it and commits if the closure succeeded.
+    /// If the closure returns an error the transaction is rolled back and the error is returned.
+    /// Serialization failures and deadlocks are retried up to [`TX_RETRIES`] times, so the
+    /// closure may run more than once and must clone anything it moves into its future.
+    /// #Example:
+    /// ```
+    /// state.with_tx(move |tx| {
+    ///     Box::pin(async move {
+    ///         sqlx::query("DELETE FROM answers;").execute(&mut **tx).await?;
+    ///         Ok(())
+    ///     })
+    /// })
+    /// .await?;
+    /// ```
+    pub async fn with_tx<T, F>(&self, mut f: F) -> Result<T, Box<dyn Error>>
+    where
+        T: Send,
+        F: for<'c> FnMut(&'c mut Transaction<'static, Postgres>) -> TxFuture<'c, T>,
+    {
+        let mut attempt = 0;
+        loop {
+            let mut tx = Pool::begin(&self.0).await?;
+            let result = match f(&mut tx).await {
+                Ok(value) => tx.commit().await.map(|_| value),
+                Err(error) => {
+                    tx.rollback().await?;
+                    Err(error)
+                }
+            };
+            match result {
+                Err(error) if attempt < TX_RETRIES && is_retryable(&error) => {
+                    attempt += 1;
+                    tracing::warn!("retrying transaction (attempt {}): {}", attempt, error);
+                }
+                result => return result.map_err(Into::into),
+            }
+        }
+    }
 
-        let tags: Option<Vec<String>> = row.try_get("tags")?;
-        let tags = tags.map(|tags| tags.into_iter().collect::<HashSet<String>>());
+    /// Function to begin a request scoped transaction, see [`RequestTx`]
+    pub async fn begin(&self) -> Result<RequestTx, sqlx::Error> {
+        Ok(RequestTx(Pool::begin(&self.0).await?))
+    }
 
-        Ok(Some(Question {
-            id: QuestionId(row.get(0)),
-            title: row.get(1),
-            content: row.get(2),
-            tags,
-        }))
+    /// Function to check that the database can be reached
+    pub async fn ping(&self) -> Result<(), Box<dyn Error>> {
+        sqlx::query(r#"SELECT 1;"#).execute(&self.0).await?;
+        Ok(())
     }
 
-    /// Function to get all questions from the database
+    /// Function to get a question from the questions database, by id, with its answer count
+    ///
+    /// The count lets a detail page show how many answers there are before loading them
+    pub async fn get_question(&self, id: &QuestionId) -> Result<Option<Question>, Box<dyn Error>> {
+        let row = match sqlx::query(
+            r#"SELECT q.*,
+            (SELECT COUNT(*) FROM answers a WHERE a.corresponding_question = q.id) AS answer_count,
+            q.accepted_answer_id IS NOT NULL AS has_accepted_answer
+            FROM questions q WHERE q.id = $1;"#,
+        )
+        .bind(id.0)
+        .fetch_optional(&self.0)
+        .await?
+        {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+
+        Ok(Some(question_with_count_from_row(&row)?))
+    }
+
+    /// Function to get all questions from the database, each with its answer count, pinned questions first
     pub async fn get_all_questions(&self) -> Result<Vec<Question>, Box<dyn Error>> {
-        let mut questions = Vec::new();
-        let rows = sqlx::query(r#"SELECT * FROM questions;"#)
-            .fetch_all(&self.0)
-            .await?;
+        let rows = sqlx::query(&format!(
+            r#"{} ORDER BY pinned DESC, id;"#,
+            QUESTIONS_WITH_ANSWER_COUNT
+        ))
+        .fetch_all(&self.0)
+        .await?;
+        let questions = rows
+            .iter()
+            .map(question_with_count_from_row)
+            .collect::<Result<Vec<Question>, sqlx::Error>>()?;
+        Ok(questions)
+    }
+
+    /// Function to get a page of questions from the database, ordered by id or by latest activity
+    /// with pinned questions first
+    ///
+    /// If a category is given only questions in that category are returned.
+    /// Each question comes with its answer count
+    pub async fn get_questions_page(
+        &self,
+        limit: i64,
+        offset: i64,
+        category: Option<Category>,
+        sort: QuestionSort,
+    ) -> Result<Vec<Question>, Box<dyn Error>> {
+        let rows = sqlx::query(&format!(
+            r#"{} WHERE ($3::text IS NULL OR category = $3)
+            ORDER BY {} LIMIT $1 OFFSET $2;"#,
+            QUESTIONS_WITH_ANSWER_COUNT,
+            question_order_by(sort)
+        ))
+        .bind(limit)
+        .bind(offset)
+        .bind(category)
+        .fetch_all(&self.0)
+        .await?;
+        let questions = rows
+            .iter()
+            .map(question_with_count_from_row)
+            .collect::<Result<Vec<Question>, sqlx::Error>>()?;
+        Ok(questions)
+    }
+
+    /// Function to get a page of the questions carrying every tag in `include` and none of the tags in `exclude`
+    ///
+    /// Either list may be empty, an empty `include` matches every question, questions without tags included.
+    /// Ordered and paged like `get_questions_page`
+    pub async fn filter_questions_by_tags(
+        &self,
+        include: &[String],
+        exclude: &[String],
+        limit: i64,
+        offset: i64,
+        category: Option<Category>,
+        sort: QuestionSort,
+    ) -> Result<Vec<Question>, Box<dyn Error>> {
+        let rows = sqlx::query(&format!(
+            r#"{} WHERE (cardinality($3::text[]) = 0 OR q.tags @> $3)
+            AND NOT (COALESCE(q.tags, '{{}}') && $4::text[])
+            AND ($5::text IS NULL OR category = $5)
+            ORDER BY {} LIMIT $1 OFFSET $2;"#,
+            QUESTIONS_WITH_ANSWER_COUNT,
+            question_order_by(sort)
+        ))
+        .bind(limit)
+        .bind(offset)
+        .bind(include)
+        .bind(exclude)
+        .bind(category)
+        .fetch_all(&self.0)
+        .await?;
+        let questions = rows
+            .iter()
+            .map(question_with_count_from_row)
+            .collect::<Result<Vec<Question>, sqlx::Error>>()?;
+        Ok(questions)
+    }
+
+    /// Function to get a page of question summaries, only the columns a light list view shows are selected
+    ///
+    /// Filtered, ordered and paged like `filter_questions_by_tags`, empty tag lists do not filter anything
+    pub async fn question_summaries_page(
+        &self,
+        include: &[String],
+        exclude: &[String],
+        limit: i64,
+        offset: i64,
+        category: Option<Category>,
+        sort: QuestionSort,
+    ) -> Result<Vec<QuestionSummary>, Box<dyn Error>> {
+        let rows = sqlx::query(&format!(
+            r#"SELECT q.id, q.title, q.tags, COALESCE(counts.answer_count, 0) AS answer_count
+            FROM questions q
+            LEFT JOIN (
+                SELECT corresponding_question, COUNT(*) AS answer_count FROM answers GROUP BY corresponding_question
+            ) counts ON counts.corresponding_question = q.id
+            WHERE (cardinality($3::text[]) = 0 OR q.tags @> $3)
+            AND NOT (COALESCE(q.tags, '{{}}') && $4::text[])
+            AND ($5::text IS NULL OR category = $5)
+            ORDER BY {} LIMIT $1 OFFSET $2;"#,
+            question_order_by(sort)
+        ))
+        .bind(limit)
+        .bind(offset)
+        .bind(include)
+        .bind(exclude)
+        .bind(category)
+        .fetch_all(&self.0)
+        .await?;
+        let mut summaries = Vec::new();
         for row in rows {
             let tags: Option<Vec<String>> = row.try_get("tags")?;
-            let tags = tags.map(|tags| tags.into_iter().collect::<HashSet<String>>());
-            questions.push(Question {
-                id: QuestionId(row.get(0)),
-                title: row.get(1),
-                content: row.get(2),
-                tags,
+            summaries.push(QuestionSummary {
+                id: QuestionId(row.try_get("id")?),
+                title: row.try_get("title")?,
+                tags: tags.map(|tags| tags.into_iter().collect::<HashSet<String>>()),
+                answer_count: row.try_get("answer_count")?,
             });
         }
+        Ok(summaries)
+    }
+
+    /// Function to count the questions a page from `get_questions_page` or `filter_questions_by_tags` is taken from
+    ///
+    /// Empty tag lists do not filter anything, so with no tags and no category every question is counted
+    pub async fn count_questions(
+        &self,
+        include: &[String],
+        exclude: &[String],
+        category: Option<Category>,
+    ) -> Result<i64, Box<dyn Error>> {
+        let total: i64 = sqlx::query_scalar(
+            r#"SELECT COUNT(*) FROM questions q WHERE (cardinality($1::text[]) = 0 OR q.tags @> $1)
+            AND NOT (COALESCE(q.tags, '{}') && $2::text[])
+            AND ($3::text IS NULL OR category = $3);"#,
+        )
+        .bind(include)
+        .bind(exclude)
+        .bind(category)
+        .fetch_one(&self.0)
+        .await?;
+        Ok(total)
+    }
+
+    /// Function to get the questions with an id above `after`, ordered by id
+    ///
+    /// Unlike an offset, the cursor `after` does not shift when questions are added or deleted between pages.
+    /// Pinned questions are not moved to the front here, as that would break the id order the cursor relies on
+    pub async fn questions_after_cursor(
+        &self,
+        after: &QuestionId,
+        limit: i64,
+        category: Option<Category>,
+    ) -> Result<Vec<Question>, Box<dyn Error>> {
+        let rows = sqlx::query(&format!(
+            r#"{} WHERE q.id > $1 AND ($3::text IS NULL OR category = $3)
+            ORDER BY q.id LIMIT $2;"#,
+            QUESTIONS_WITH_ANSWER_COUNT
+        ))
+        .bind(after.0)
+        .bind(limit)
+        .bind(category)
+        .fetch_all(&self.0)
+        .await?;
+        let questions = rows
+            .iter()
+            .map(question_with_count_from_row)
+            .collect::<Result<Vec<Question>, sqlx::Error>>()?;
         Ok(questions)
     }
 
-    /// Function to add a question to the questions database
-    pub async fn add_question(self, question: Question) -> Result<(), Box<dyn Error>> {
-        let tx = Pool::begin(&self.0).await?;
-        let tags = question
-            .tags
-            .map(|tags| tags.into_iter().collect::<Vec<String>>());
-        sqlx::query(r#"INSERT INTO questions (title, content, tags) VALUES ($1, $2, $3);"#)
-            .bind(question.title)
-            .bind(question.content)
-            .bind(&tags)
-            .execute(&self.0)
-            .await?;
+    /// Function to get a page of the questions that have no answers yet, pinned first then newest first
+    pub async fn unanswered_questions(
+        &self,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Question>, Box<dyn Error>> {
+        let rows = sqlx::query(
+            r#"SELECT q.* FROM questions q
+            LEFT JOIN answers a ON a.corresponding_question = q.id
+            WHERE a.id IS NULL
+            ORDER BY q.pinned DESC, q.created_on DESC, q.id DESC
+            LIMIT $1 OFFSET $2;"#,
+        )
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.0)
+        .await?;
+        let questions = rows
+            .iter()
+            .map(question_from_row)
+            .collect::<Result<Vec<Question>, sqlx::Error>>()?;
+        Ok(questions)
+    }
 
-        Ok(tx.commit().await?)
+    /// Function to get the questions with the most answers posted in the last `hours`, most first
+    ///
+    /// Ties go to the question with the latest activity, questions without recent answers are left out
+    pub async fn trending_questions(
+        &self,
+        hours: i32,
+        limit: i64,
+    ) -> Result<Vec<Question>, Box<dyn Error>> {
+        let rows = sqlx::query(&format!(
+            r#"{}
+            JOIN (
+                SELECT corresponding_question, COUNT(*) AS recent_answers FROM answers
+                WHERE created_at >= NOW() - make_interval(hours => $1)
+                GROUP BY corresponding_question
+            ) recent ON recent.corresponding_question = q.id
+            ORDER BY recent.recent_answers DESC, q.last_activity_at DESC, q.id DESC
+            LIMIT $2;"#,
+            QUESTIONS_WITH_ANSWER_COUNT
+        ))
+        .bind(hours)
+        .bind(limit)
+        .fetch_all(&self.0)
+        .await?;
+        let questions = rows
+            .iter()
+            .map(question_with_count_from_row)
+            .collect::<Result<Vec<Question>, sqlx::Error>>()?;
+        Ok(questions)
     }
 
-    /// Function to delete a question from the questions database
-    pub async fn delete_question(self, id: &QuestionId) -> Result<(), Box<dyn Error>> {
-        let tx = Pool::begin(&self.0).await?;
-        sqlx::query(r#"DELETE FROM questions WHERE id = $1;"#)
-            .bind(id.0)
-            .execute(&self.0)
-            .await?;
-        sqlx::query(r#"DELETE FROM answers WHERE corresponding_question = $1;"#)
-            .bind(id.0)
-            .execute(&self.0)
+    /// Function to get the most recently asked questions for the feed, newest first
+    pub async fn feed_entries(&self, limit: i64) -> Result<Vec<FeedEntry>, Box<dyn Error>> {
+        let rows = sqlx::query(
+            r#"SELECT id, title, created_on FROM questions ORDER BY created_on DESC, id DESC LIMIT $1;"#,
+        )
+        .bind(limit)
+        .fetch_all(&self.0)
+        .await?;
+        let entries = rows
+            .iter()
+            .map(|row| {
+                Ok(FeedEntry {
+                    id: QuestionId(row.try_get("id")?),
+                    title: row.try_get("title")?,
+                    created_on: row.try_get("created_on")?,
+                })
+            })
+            .collect::<Result<Vec<FeedEntry>, sqlx::Error>>()?;
+        Ok(entries)
+    }
+
+    /// Function to find questions whose title is similar to the given one, most similar first
+    ///
+    /// Similarity is pg_trgm's trigram similarity, between 0 and 1, and must exceed `threshold`.
+    /// Each question comes with its answer count, so the ones already answered can be told apart
+    pub async fn similar_titles(
+        &self,
+        title: &str,
+        threshold: f32,
+        limit: i64,
+    ) -> Result<Vec<Question>, Box<dyn Error>> {
+        let rows = sqlx::query(&format!(
+            r#"{} WHERE similarity(title, $1) > $2
+            ORDER BY similarity(title, $1) DESC LIMIT $3;"#,
+            QUESTIONS_WITH_ANSWER_COUNT
+        ))
+        .bind(title)
+        .bind(threshold)
+        .bind(limit)
+        .fetch_all(&self.0)
+        .await?;
+        let questions = rows
+            .iter()
+            .map(question_with_count_from_row)
+            .collect::<Result<Vec<Question>, sqlx::Error>>()?;
+        Ok(questions)
+    }
+
+    /// Function to get a page of the tags in use with how many questions carry each
+    ///
+    /// Only tags starting with `prefix` are counted when one is given, ignoring case
+    pub async fn tag_counts(
+        &self,
+        prefix: Option<&str>,
+        sort: TagSort,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<TagCount>, Box<dyn Error>> {
+        let order_by = match sort {
+            TagSort::Frequency => "count DESC, tag",
+            TagSort::Alpha => "tag",
+        };
+        let prefix = prefix.map(|prefix| format!("{}%", escape_like(prefix)));
+        let rows = sqlx::query(&format!(
+            r#"SELECT tag, COUNT(*) AS count FROM questions, unnest(tags) AS tag
+            WHERE ($1::text IS NULL OR tag ILIKE $1)
+            GROUP BY tag ORDER BY {} LIMIT $2 OFFSET $3;"#,
+            order_by
+        ))
+        .bind(prefix)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.0)
+        .await?;
+        let tags = rows
+            .iter()
+            .map(|row| {
+                Ok(TagCount {
+                    tag: row.try_get("tag")?,
+                    count: row.try_get("count")?,
+                })
+            })
+            .collect::<Result<Vec<TagCount>, sqlx::Error>>()?;
+        Ok(tags)
+    }
+
+    /// Function to get the earlier revisions of a question, newest first
+    pub async fn get_question_history(
+        &self,
+        id: &QuestionId,
+    ) -> Result<Vec<QuestionRevision>, Box<dyn Error>> {
+        let mut revisions = Vec::new();
+        let rows = sqlx::query(
+            r#"SELECT * FROM question_revisions WHERE question_id = $1 ORDER BY edited_at DESC, id DESC;"#,
+        )
+        .bind(id.0)
+        .fetch_all(&self.0)
+        .await?;
+        for row in rows {
+            let tags: Option<Vec<String>> = row.try_get("tags")?;
+            revisions.push(QuestionRevision {
+                question_id: QuestionId(row.try_get("question_id")?),
+                title: row.try_get("title")?,
+                content: row.try_get("content")?,
+                tags: tags.map(|tags| tags.into_iter().collect::<HashSet<String>>()),
+                edited_at: row.try_get("edited_at")?,
+            });
+        }
+        Ok(revisions)
+    }
+
+    /// Function to append an event to the events log, on the transaction making the change so both commit or neither does
+    pub async fn record_event(
+        tx: &mut Transaction<'static, Postgres>,
+        entity_type: &str,
+        entity_id: i32,
+        action: EventAction,
+        actor_id: Option<i32>,
+        payload: serde_json::Value,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(
+            r#"INSERT INTO events (entity_type, entity_id, action, actor_id, payload)
+            VALUES ($1, $2, $3, $4, $5);"#,
+        )
+        .bind(entity_type)
+        .bind(entity_id)
+        .bind(action)
+        .bind(actor_id)
+        .bind(sqlx::types::Json(payload))
+        .execute(&mut **tx)
+        .await?;
+        Ok(())
+    }
+
+    /// Function to get a page of the events log, newest first, optionally only the events of one entity type
+    pub async fn get_events(
+        &self,
+        entity_type: Option<&str>,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<Event>, Box<dyn Error>> {
+        let rows = sqlx::query(
+            r#"SELECT * FROM events WHERE $1::TEXT IS NULL OR entity_type = $1
+            ORDER BY id DESC LIMIT $2 OFFSET $3;"#,
+        )
+        .bind(entity_type)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.0)
+        .await?;
+        let mut events = Vec::new();
+        for row in rows {
+            let payload: sqlx::types::Json<serde_json::Value> = row.try_get("payload")?;
+            events.push(Event {
+                id: row.try_get("id")?,
+                entity_type: row.try_get("entity_type")?,
+                entity_id: row.try_get("entity_id")?,
+                action: row.try_get("action")?,
+                actor_id: row.try_get("actor_id")?,
+                created_at: row.try_get("created_at")?,
+                payload: payload.0,
+            });
+        }
+        Ok(events)
+    }
+
+    /// Function to file a report against an answer, returning the stored report
+    pub async fn add_answer_report(
+        &self,
+        answer_id: &AnswerId,
+        reporter_id: i32,
+        reason: &str,
+    ) -> Result<Report, Box<dyn Error>> {
+        let (answer_id, reason) = (answer_id.0, reason.to_string());
+        self.with_tx(move |tx| {
+            let reason = reason.clone();
+            Box::pin(async move {
+                let row = sqlx::query(
+                    r#"INSERT INTO reports (answer_id, reporter_id, reason) VALUES ($1, $2, $3) RETURNING *;"#,
+                )
+                .bind(answer_id)
+                .bind(reporter_id)
+                .bind(&reason)
+                .fetch_one(&mut **tx)
+                .await?;
+                let report = report_from_row(&row)?;
+                let payload = serde_json::json!({ "answer_id": answer_id, "reason": reason });
+                AppState::record_event(
+                    tx,
+                    "report",
+                    report.id,
+                    EventAction::Create,
+                    Some(reporter_id),
+                    payload,
+                )
+                .await?;
+                Ok(report)
+            })
+        })
+        .await
+    }
+
+    /// Function to list every report, question and answer reports alike, newest first
+    pub async fn get_reports(&self) -> Result<Vec<Report>, Box<dyn Error>> {
+        let rows = sqlx::query(r#"SELECT * FROM reports ORDER BY id DESC;"#)
+            .fetch_all(&self.0)
             .await?;
-        Ok(tx.commit().await?)
+        Ok(rows
+            .iter()
+            .map(report_from_row)
+            .collect::<Result<Vec<Report>, sqlx::Error>>()?)
     }
 
-    /// Function to update a question in the questions database
-    pub async fn update_question(
+    /// Function to list the migrations sqlx has applied to the database, oldest first
+    pub async fn applied_migrations(&self) -> Result<Vec<AppliedMigration>, Box<dyn Error>> {
+        let rows = sqlx::query(
+            r#"SELECT version, description, installed_on FROM _sqlx_migrations
+            WHERE success ORDER BY version;"#,
+        )
+        .fetch_all(&self.0)
+        .await?;
+        let mut migrations = Vec::new();
+        for row in rows {
+            migrations.push(AppliedMigration {
+                version: row.try_get("version")?,
+                description: row.try_get("description")?,
+                installed_on: row.try_get("installed_on")?,
+            });
+        }
+        Ok(migrations)
+    }
+
+    /// Function to delete the expired sessions from the session store table
+    ///
+    /// Returns the number of sessions purged
+    pub async fn purge_expired_sessions(&self) -> Result<u64, Box<dyn Error>> {
+        let result =
+            sqlx::query(r#"DELETE FROM tower_sessions.session WHERE expiry_date < now();"#)
+                .execute(&self.0)
+                .await?;
+        Ok(result.rows_affected())
+    }
+
+    /// Function to add a question to the questions database
+    ///
+    /// The author is the account of the logged in user, or None for an anonymous question
+    pub async fn add_question(
         self,
-        id: &QuestionId,
         question: Question,
+        author_id: Option<i32>,
     ) -> Result<(), Box<dyn Error>> {
-        let tx = Pool::begin(&self.0).await?;
-        let tags = question
-            .tags
-            .map(|tags| tags.into_iter().collect::<Vec<String>>());
-        sqlx::query(r#"UPDATE questions SET title = $1, content = $2, tags = $3 WHERE id = $4;"#)
-            .bind(question.title)
-            .bind(question.content)
-            .bind(tags)
-            .bind(id.0)
-            .execute(&self.0)
-            .await?;
-        Ok(tx.commit().await?)
+        self.with_tx(move |tx| {
+            let question = question.clone();
+            Box::pin(async move {
+                insert_question(tx, &question, author_id).await?;
+                Ok(())
+            })
+        })
+        .await
     }
 
-    pub async fn add_answer(self, answer: Answer) -> Result<(), Box<dyn Error>> {
-        let tx = Pool::begin(&self.0).await?;
-        sqlx::query(r#"INSERT INTO answers (corresponding_question, content) VALUES ($1, $2);"#)
-            .bind(answer.question_id.0)
-            .bind(answer.content)
-            .execute(&self.0)
-            .await?;
-        Ok(tx.commit().await?)
+    /// Function to delete a question, and its answers, from the questions database
+    ///
+    /// Returns the number of questions deleted, so zero means no question matched the id
+    pub async fn delete_question(
+        self,
+        id: &QuestionId,
+        actor_id: Option<i32>,
+    ) -> Result<u64, Box<dyn Error>> {
+        let id = id.0;
+        self.with_tx(move |tx| {
+            Box::pin(async move {
+                let answers: Vec<(i32, i32)> = sqlx::query_as(
+                    r#"DELETE FROM answers WHERE corresponding_question = $1
+                    RETURNING id, corresponding_question;"#,
+                )
+                .bind(id)
+                .fetch_all(&mut **tx)
+                .await?;
+                record_answer_deletes(tx, &answers, actor_id, "question").await?;
+                let result = sqlx::query(r#"DELETE FROM questions WHERE id = $1;"#)
+                    .bind(id)
+                    .execute(&mut **tx)
+                    .await?;
+                if result.rows_affected() > 0 {
+                    let payload = serde_json::json!({ "answers_deleted": answers.len() });
+                    AppState::record_event(
+                        tx,
+                        "question",
+                        id,
+                        EventAction::Delete,
+                        actor_id,
+                        payload,
+                    )
+                    .await?;
+                }
+                Ok(result.rows_affected())
+            })
+        })
+        .await
+    }
+
+    /// Function to update a question in the questions database
+    ///
+    /// Returns the number of questions updated, so zero means no question matched the id
+    pub async fn update_question(
+        self,
+        id: &QuestionId,
+        question: Question,
+        actor_id: Option<i32>,
+    ) -> Result<u64, Box<dyn Error>> {
+        let id = id.0;
+        let tags = tags_column(&question.tags);
+        self.with_tx(move |tx| {
+            let question = question.clone();
+            let tags = tags.clone();
+            Box::pin(async move {
+                // Keep the state before this edit around for the question history
+                sqlx::query(
+                    r#"INSERT INTO question_revisions (question_id, title, content, tags)
+                    SELECT id, title, content, tags FROM questions WHERE id = $1;"#,
+                )
+                .bind(id)
+                .execute(&mut **tx)
+                .await?;
+                let result = sqlx::query(
+                    r#"UPDATE questions SET title = $1, content = $2, tags = $3, category = $4,
+                    last_activity_at = NOW() WHERE id = $5;"#,
+                )
+                .bind(&question.title)
+                .bind(&question.content)
+                .bind(&tags)
+                .bind(question.category)
+                .bind(id)
+                .execute(&mut **tx)
+                .await?;
+                if result.rows_affected() > 0 {
+                    let payload = serde_json::json!({
+                        "title": question.title,
+                        "content": question.content,
+                        "tags": tags,
+                        "category": question.category,
+                    });
+                    AppState::record_event(
+                        tx,
+                        "question",
+                        id,
+                        EventAction::Update,
+                        actor_id,
+                        payload,
+                    )
+                    .await?;
+                }
+                Ok(result.rows_affected())
+            })
+        })
+        .await
     }
 
+    /// Function to get a page of the answers to a question
+    ///
+    /// Ties, such as answers with the same score, fall back to oldest first
     pub async fn get_answers(
         &self,
         question_id: &QuestionId,
+        limit: i64,
+        offset: i64,
+        sort: AnswerSort,
     ) -> Result<Vec<Answer>, Box<dyn Error>> {
-        let mut answers = Vec::new();
-        let rows = sqlx::query(r#"SELECT * FROM answers WHERE corresponding_question = $1;"#)
-            .bind(question_id.0)
-            .fetch_all(&self.0)
-            .await?;
-        for row in rows {
-            answers.push(Answer {
-                content: row.get("content"),
-                question_id: QuestionId(row.get("corresponding_question")),
-            });
-        }
+        let order_by = match sort {
+            AnswerSort::Oldest => "a.id",
+            AnswerSort::Newest => "a.id DESC",
+            AnswerSort::Votes => "COALESCE(scores.score, 0) DESC, a.id",
+        };
+        let rows = sqlx::query(&format!(
+            r#"SELECT a.*, COALESCE(q.accepted_answer_id = a.id, false) AS is_accepted, {}
+            FROM answers a JOIN questions q ON q.id = a.corresponding_question
+            LEFT JOIN (
+                SELECT answer_id, SUM(value) AS score FROM answer_votes GROUP BY answer_id
+            ) scores ON scores.answer_id = a.id
+            WHERE a.corresponding_question = $1 ORDER BY {} LIMIT $2 OFFSET $3;"#,
+            author_name("a.author_id"),
+            order_by
+        ))
+        .bind(question_id.0)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.0)
+        .await?;
+        let answers = rows
+            .iter()
+            .map(answer_from_row)
+            .collect::<Result<Vec<Answer>, sqlx::Error>>()?;
         Ok(answers)
     }
 
-    pub async fn delete_answer(self, question_id: &QuestionId) -> Result<(), Box<dyn Error>> {
-        let tx = Pool::begin(&self.0).await?;
-        sqlx::query(r#"DELETE FROM answers WHERE corresponding_question = $1;"#)
-            .bind(question_id.0)
-            .execute(&self.0)
+    /// Function to get the answers to several questions at once, grouped by question
+    pub async fn get_answers_for_questions(
+        &self,
+        question_ids: &[i32],
+    ) -> Result<Vec<Answer>, Box<dyn Error>> {
+        let rows = sqlx::query(&format!(
+            r#"SELECT a.*, COALESCE(q.accepted_answer_id = a.id, false) AS is_accepted, {}
+            FROM answers a JOIN questions q ON q.id = a.corresponding_question
+            WHERE a.corresponding_question = ANY($1)
+            ORDER BY a.corresponding_question, a.id;"#,
+            author_name("a.author_id")
+        ))
+        .bind(question_ids)
+        .fetch_all(&self.0)
+        .await?;
+        let answers = rows
+            .iter()
+            .map(answer_from_row)
+            .collect::<Result<Vec<Answer>, sqlx::Error>>()?;
+        Ok(answers)
+    }
+
+    /// Function to get a question together with its answers
+    ///
+    /// When an account id is given, `answered_by_me` tells if that account has answered the question
+    pub async fn get_question_with_answers(
+        &self,
+        id: &QuestionId,
+        account_id: Option<i32>,
+    ) -> Result<QuestionWithAnswers, Box<dyn Error>> {
+        let question = self.get_question(id).await?.ok_or("Question not found")?;
+        let answers = self
+            .get_answers(id, self.2.max_page_size, 0, self.2.answer_sort_default)
             .await?;
-        Ok(tx.commit().await?)
+        let answered_by_me = match account_id {
+            Some(account_id) => {
+                sqlx::query_scalar(
+                    r#"SELECT EXISTS(SELECT 1 FROM answers WHERE corresponding_question = $1 AND author_id = $2);"#,
+                )
+                .bind(id.0)
+                .bind(account_id)
+                .fetch_one(&self.0)
+                .await?
+            }
+            None => false,
+        };
+        Ok(QuestionWithAnswers {
+            question,
+            answers,
+            answered_by_me,
+        })
+    }
+
+    /// Function to count the answers of a question
+    pub async fn count_answers(&self, question_id: &QuestionId) -> Result<i64, Box<dyn Error>> {
+        let count: i64 = sqlx::query_scalar(
+            r#"SELECT COUNT(*) FROM answers WHERE corresponding_question = $1;"#,
+        )
+        .bind(question_id.0)
+        .fetch_one(&self.0)
+        .await?;
+        Ok(count)
+    }
+
+    /// Function to get the question an answer belongs to, or None if there is no such answer
+    pub async fn get_answer_question(
+        &self,
+        answer_id: &AnswerId,
+    ) -> Result<Option<QuestionId>, Box<dyn Error>> {
+        let question_id: Option<i32> =
+            sqlx::query_scalar(r#"SELECT corresponding_question FROM answers WHERE id = $1;"#)
+                .bind(answer_id.0)
+                .fetch_optional(&self.0)
+                .await?;
+        Ok(question_id.map(QuestionId))
+    }
+
+    /// Function to record an account's vote on an answer, returning the answer's new score
+    ///
+    /// Each account has a single vote per answer, voting again replaces it instead of adding to it
+    pub async fn vote_answer(
+        self,
+        answer_id: &AnswerId,
+        account_id: i32,
+        value: i16,
+    ) -> Result<i64, Box<dyn Error>> {
+        let answer_id = answer_id.0;
+        self.with_tx(move |tx| {
+            Box::pin(async move {
+                sqlx::query(
+                    r#"INSERT INTO answer_votes (answer_id, account_id, value) VALUES ($1, $2, $3)
+                    ON CONFLICT (answer_id, account_id) DO UPDATE SET value = EXCLUDED.value;"#,
+                )
+                .bind(answer_id)
+                .bind(account_id)
+                .bind(value)
+                .execute(&mut **tx)
+                .await?;
+                let payload = serde_json::json!({ "vote": value });
+                AppState::record_event(
+                    tx,
+                    "answer",
+                    answer_id,
+                    EventAction::Update,
+                    Some(account_id),
+                    payload,
+                )
+                .await?;
+                let score: i64 = sqlx::query_scalar(
+                    r#"SELECT COALESCE(SUM(value), 0)::bigint FROM answer_votes WHERE answer_id = $1;"#,
+                )
+                .bind(answer_id)
+                .fetch_one(&mut **tx)
+                .await?;
+                Ok(score)
+            })
+        })
+        .await
+    }
+
+    /// Function to check if a question exists, without fetching the row
+    pub async fn question_exists(&self, id: &QuestionId) -> Result<bool, Box<dyn Error>> {
+        let exists: bool =
+            sqlx::query_scalar(r#"SELECT EXISTS(SELECT 1 FROM questions WHERE id = $1);"#)
+                .bind(id.0)
+                .fetch_one(&self.0)
+                .await?;
+        Ok(exists)
+    }
+
+    /// Function to check if an answer exists for the given id, without fetching the row
+    pub async fn answer_exists(&self, id: &AnswerId) -> Result<bool, Box<dyn Error>> {
+        let exists: bool =
+            sqlx::query_scalar(r#"SELECT EXISTS(SELECT 1 FROM answers WHERE id = $1);"#)
+                .bind(id.0)
+                .fetch_one(&self.0)
+                .await?;
+        Ok(exists)
+    }
+
+    /// Function to get the account that wrote an answer, None if there is no such answer
+    ///
+    /// The inner None is an answer posted anonymously
+    pub async fn answer_author(
+        &self,
+        id: &AnswerId,
+    ) -> Result<Option<Option<i32>>, Box<dyn Error>> {
+        let author: Option<Option<i32>> =
+            sqlx::query_scalar(r#"SELECT author_id FROM answers WHERE id = $1;"#)
+                .bind(id.0)
+                .fetch_optional(&self.0)
+                .await?;
+        Ok(author)
+    }
+
+    /// Function to check if an answer was posted more than `secs` seconds ago
+    pub async fn answer_edit_window_expired(
+        &self,
+        id: &AnswerId,
+        secs: i64,
+    ) -> Result<bool, Box<dyn Error>> {
+        let expired: bool = sqlx::query_scalar(
+            r#"SELECT EXISTS(SELECT 1 FROM answers WHERE id = $1
+            AND created_at < NOW() - make_interval(secs => $2));"#,
+        )
+        .bind(id.0)
+        .bind(secs as f64)
+        .fetch_one(&self.0)
+        .await?;
+        Ok(expired)
+    }
+
+    /// Function to delete an answer, returning the number of answers deleted
+    ///
+    /// The answer's replies are deleted with it and logged too. Their votes go with them through the
+    /// ON DELETE CASCADE foreign key on answer_votes, so no vote rows are left behind
+    pub async fn delete_answer(
+        self,
+        id: &AnswerId,
+        actor_id: Option<i32>,
+    ) -> Result<u64, Box<dyn Error>> {
+        let id = id.0;
+        self.with_tx(move |tx| {
+            Box::pin(async move {
+                let deleted: Vec<(i32, i32)> = sqlx::query_as(
+                    r#"WITH RECURSIVE doomed AS (
+                        SELECT id FROM answers WHERE id = $1
+                        UNION SELECT a.id FROM answers a JOIN doomed d ON a.parent_answer_id = d.id
+                    )
+                    DELETE FROM answers WHERE id IN (SELECT id FROM doomed)
+                    RETURNING id, corresponding_question;"#,
+                )
+                .bind(id)
+                .fetch_all(&mut **tx)
+                .await?;
+                let (answer, replies): (Vec<(i32, i32)>, Vec<(i32, i32)>) = deleted
+                    .into_iter()
+                    .partition(|(answer_id, _)| *answer_id == id);
+                for (answer_id, question_id) in &answer {
+                    let payload = serde_json::json!({ "question_id": question_id });
+                    AppState::record_event(
+                        tx,
+                        "answer",
+                        *answer_id,
+                        EventAction::Delete,
+                        actor_id,
+                        payload,
+                    )
+                    .await?;
+                }
+                record_answer_deletes(tx, &replies, actor_id, "answer").await?;
+                Ok(answer.len() as u64)
+            })
+        })
+        .await
     }
 
+    /// Function to update an answer, returning the answer as it is after the update, or None if there is no such answer
     pub async fn update_answer(
         self,
-        question_id: &QuestionId,
+        id: &AnswerId,
         answer: Answer,
-    ) -> Result<(), Box<dyn Error>> {
-        let tx = Pool::begin(&self.0).await?;
-        sqlx::query(r#"UPDATE answers SET content = $1 WHERE corresponding_question = $2;"#)
-            .bind(answer.content)
-            .bind(question_id.0)
-            .execute(&self.0)
-            .await?;
-        Ok(tx.commit().await?)
+        actor_id: Option<i32>,
+    ) -> Result<Option<Answer>, Box<dyn Error>> {
+        let id = id.0;
+        let content_html = markdown::render(&answer.content);
+        self.with_tx(move |tx| {
+            let (answer, content_html) = (answer.clone(), content_html.clone());
+            Box::pin(async move {
+                let row = sqlx::query(&format!(
+                    r#"UPDATE answers SET content = $1, content_html = $2 WHERE id = $3
+                    RETURNING *, COALESCE(
+                        (SELECT accepted_answer_id FROM questions WHERE id = answers.corresponding_question) = answers.id,
+                        false
+                    ) AS is_accepted, {};"#,
+                    author_name("answers.author_id")
+                ))
+                .bind(&answer.content)
+                .bind(content_html)
+                .bind(id)
+                .fetch_optional(&mut **tx)
+                .await?;
+                let updated = match row {
+                    Some(row) => answer_from_row(&row)?,
+                    None => return Ok(None),
+                };
+                let payload = serde_json::json!({ "content": updated.content });
+                AppState::record_event(tx, "answer", id, EventAction::Update, actor_id, payload)
+                    .await?;
+                Ok(Some(updated))
+            })
+        })
+        .await
     }
 
+    /// Function to add an account, linking it to the login credentials that have the same email
     pub async fn add_account(self, acc: Account) -> Result<(), Box<dyn Error>> {
-        let tx = Pool::begin(&self.0).await?;
-        sqlx::query(r#"INSERT INTO accounts (email, password) VALUES ($1, $2);"#)
-            .bind(acc.email)
-            .bind(acc.password)
-            .execute(&self.0)
-            .await?;
-        Ok(tx.commit().await?)
+        self.with_tx(move |tx| {
+            let acc = acc.clone();
+            Box::pin(async move {
+                let id: i32 = sqlx::query_scalar(
+                    r#"INSERT INTO accounts (email, password, display_name) VALUES ($1, $2, NULLIF(btrim($3), ''))
+                    RETURNING id;"#,
+                )
+                .bind(&acc.email)
+                .bind(&acc.password)
+                .bind(acc.display_name)
+                .fetch_one(&mut **tx)
+                .await?;
+                // Login finds the account through this link from then on, not through the email
+                sqlx::query(
+                    r#"UPDATE passwords SET account_id = $1 WHERE email = $2 AND account_id IS NULL;"#,
+                )
+                .bind(id)
+                .bind(&acc.email)
+                .execute(&mut **tx)
+                .await?;
+                let payload = serde_json::json!({ "email": acc.email });
+                AppState::record_event(tx, "account", id, EventAction::Create, None, payload)
+                    .await?;
+                Ok(())
+            })
+        })
+        .await
+    }
+
+    /// Function to check if an account has this email, without fetching the row
+    pub async fn account_exists(&self, email: &str) -> Result<bool, Box<dyn Error>> {
+        let exists: bool =
+            sqlx::query_scalar(r#"SELECT EXISTS(SELECT 1 FROM accounts WHERE email = $1);"#)
+                .bind(email)
+                .fetch_one(&self.0)
+                .await?;
+        Ok(exists)
     }
 
     pub async fn get_account(&self, email: &str) -> Result<Option<Account>, Box<dyn Error>> {
-        let row = sqlx::query(r#"SELECT * from accounts WHERE email = $1;"#)
+        let row = match sqlx::query(r#"SELECT * from accounts WHERE email = $1;"#)
             .bind(email)
-            .fetch_one(&self.0)
-            .await?;
-
-        let email = match row.try_get("username")? {
-            Some(email) => email,
+            .fetch_optional(&self.0)
+            .await?
+        {
+            Some(row) => row,
             None => return Ok(None),
         };
+
+        let email = row.try_get("email")?;
         let password = row.try_get("password")?;
-        let account_id = row.try_get("id")?;
-        if let Some(id) = account_id {
-            Ok(Some(Account {
-                id,
-                email,
-                password,
-            }))
-        } else {
-            Ok(None)
-        }
+        let id = row.try_get("id")?;
+        let display_name = row.try_get("display_name")?;
+        Ok(Some(Account {
+            id,
+            email,
+            password,
+            display_name,
+        }))
     }
 
-    pub async fn delete_account(self, email: &str) -> Result<(), Box<dyn Error>> {
-        let tx = Pool::begin(&self.0).await?;
-        sqlx::query(r#"DELETE FROM accounts WHERE email = $1;"#)
-            .bind(email)
-            .execute(&self.0)
+    /// Function to get an account by its id, or None if there is no such account
+    pub async fn get_account_by_id(
+        &self,
+        id: &AccountId,
+    ) -> Result<Option<Account>, Box<dyn Error>> {
+        let row = match sqlx::query(
+            r#"SELECT id, email, password, display_name FROM accounts WHERE id = $1;"#,
+        )
+        .bind(id.0)
+        .fetch_optional(&self.0)
+        .await?
+        {
+            Some(row) => row,
+            None => return Ok(None),
+        };
+        Ok(Some(Account {
+            id: row.try_get("id")?,
+            email: row.try_get("email")?,
+            password: row.try_get("password")?,
+            display_name: row.try_get("display_name")?,
+        }))
+    }
+
+    /// Function to search questions and answers at once for text, ignoring case
+    ///
+    /// Questions match on their title or content, answers on their content. Each section holds at most
+    /// `limit` results, newest first
+    pub async fn global_search(
+        &self,
+        query: &str,
+        limit: i64,
+    ) -> Result<SearchResults, Box<dyn Error>> {
+        let pattern = format!("%{}%", escape_like(query));
+        let question_rows = sqlx::query(&format!(
+            r#"{} WHERE q.title ILIKE $1 OR q.content ILIKE $1 ORDER BY q.id DESC LIMIT $2;"#,
+            QUESTIONS_WITH_ANSWER_COUNT
+        ))
+        .bind(&pattern)
+        .bind(limit)
+        .fetch_all(&self.0)
+        .await?;
+        let answer_rows = sqlx::query(&format!(
+            r#"SELECT a.*, COALESCE(q.accepted_answer_id = a.id, false) AS is_accepted, {}
+            FROM answers a JOIN questions q ON q.id = a.corresponding_question
+            WHERE a.content ILIKE $1 ORDER BY a.id DESC LIMIT $2;"#,
+            author_name("a.author_id")
+        ))
+        .bind(&pattern)
+        .bind(limit)
+        .fetch_all(&self.0)
+        .await?;
+        Ok(SearchResults {
+            questions: question_rows
+                .iter()
+                .map(question_with_count_from_row)
+                .collect::<Result<Vec<Question>, sqlx::Error>>()?,
+            answers: answer_rows
+                .iter()
+                .map(answer_from_row)
+                .collect::<Result<Vec<Answer>, sqlx::Error>>()?,
+        })
+    }
+
+    /// Function to get a page of the accounts whose email contains `query`, ignoring case, ordered by id
+    pub async fn search_accounts(
+        &self,
+        query: &str,
+        limit: i64,
+        offset: i64,
+    ) -> Result<Vec<PublicAccount>, Box<dyn Error>> {
+        let pattern = format!("%{}%", escape_like(query));
+        let rows = sqlx::query(
+            r#"SELECT id, email, display_name FROM accounts WHERE email ILIKE $1
+            ORDER BY id LIMIT $2 OFFSET $3;"#,
+        )
+        .bind(pattern)
+        .bind(limit)
+        .bind(offset)
+        .fetch_all(&self.0)
+        .await?;
+        let accounts = rows
+            .iter()
+            .map(|row| {
+                Ok(PublicAccount {
+                    id: AccountId(row.try_get("id")?),
+                    email: row.try_get("email")?,
+                    display_name: row.try_get("display_name")?,
+                })
+            })
+            .collect::<Result<Vec<PublicAccount>, sqlx::Error>>()?;
+        Ok(accounts)
+    }
+
+    /// Function to make an account an admin, returning the number of accounts promoted
+    pub async fn promote_account(
+        self,
+        id: &AccountId,
+        actor_id: Option<i32>,
+    ) -> Result<u64, Box<dyn Error>> {
+        let id = id.0;
+        self.with_tx(move |tx| {
+            Box::pin(async move {
+                let result = sqlx::query(r#"UPDATE accounts SET is_admin = true WHERE id = $1;"#)
+                    .bind(id)
+                    .execute(&mut **tx)
+                    .await?;
+                if result.rows_affected() > 0 {
+                    let payload = serde_json::json!({ "is_admin": true });
+                    AppState::record_event(
+                        tx,
+                        "account",
+                        id,
+                        EventAction::Update,
+                        actor_id,
+                        payload,
+                    )
+                    .await?;
+                }
+                Ok(result.rows_affected())
+            })
+        })
+        .await
+    }
+
+    /// Function to pin or unpin a question
+    ///
+    /// Returns the number of questions changed, 0 when there is no such question
+    pub async fn set_question_pinned(
+        self,
+        id: &QuestionId,
+        pinned: bool,
+        actor_id: Option<i32>,
+    ) -> Result<u64, Box<dyn Error>> {
+        let id = id.0;
+        self.with_tx(move |tx| {
+            Box::pin(async move {
+                let result = sqlx::query(r#"UPDATE questions SET pinned = $1 WHERE id = $2;"#)
+                    .bind(pinned)
+                    .bind(id)
+                    .execute(&mut **tx)
+                    .await?;
+                if result.rows_affected() > 0 {
+                    let payload = serde_json::json!({ "pinned": pinned });
+                    AppState::record_event(
+                        tx,
+                        "question",
+                        id,
+                        EventAction::Update,
+                        actor_id,
+                        payload,
+                    )
+                    .await?;
+                }
+                Ok(result.rows_affected())
+            })
+        })
+        .await
+    }
+
+    /// Function to mark a question as a duplicate of another one, returning the number of questions changed
+    pub async fn mark_duplicate(
+        self,
+        id: &QuestionId,
+        target: &QuestionId,
+        actor_id: Option<i32>,
+    ) -> Result<u64, Box<dyn Error>> {
+        let (id, target) = (id.0, target.0);
+        self.with_tx(move |tx| {
+            Box::pin(async move {
+                let result =
+                    sqlx::query(r#"UPDATE questions SET duplicate_of = $1 WHERE id = $2;"#)
+                        .bind(target)
+                        .bind(id)
+                        .execute(&mut **tx)
+                        .await?;
+                if result.rows_affected() > 0 {
+                    let payload = serde_json::json!({ "duplicate_of": target });
+                    AppState::record_event(
+                        tx,
+                        "question",
+                        id,
+                        EventAction::Update,
+                        actor_id,
+                        payload,
+                    )
+                    .await?;
+                }
+                Ok(result.rows_affected())
+            })
+        })
+        .await
+    }
+
+    /// Function to close a question with a reason, returning the number of questions changed
+    ///
+    /// Closing a closed question again replaces its reason
+    pub async fn close_question(
+        self,
+        id: &QuestionId,
+        reason: CloseReason,
+        actor_id: Option<i32>,
+    ) -> Result<u64, Box<dyn Error>> {
+        let id = id.0;
+        self.with_tx(move |tx| {
+            Box::pin(async move {
+                let result =
+                    sqlx::query(r#"UPDATE questions SET close_reason = $1 WHERE id = $2;"#)
+                        .bind(reason)
+                        .bind(id)
+                        .execute(&mut **tx)
+                        .await?;
+                if result.rows_affected() > 0 {
+                    let payload = serde_json::json!({ "close_reason": reason });
+                    AppState::record_event(
+                        tx,
+                        "question",
+                        id,
+                        EventAction::Update,
+                        actor_id,
+                        payload,
+                    )
+                    .await?;
+                }
+                Ok(result.rows_affected())
+            })
+        })
+        .await
+    }
+
+    /// Function to rename a tag on every question carrying it, returning the number of questions changed
+    ///
+    /// A question that already had the new tag keeps it only once
+    pub async fn rename_tag(
+        self,
+        from: &str,
+        to: &str,
+        actor_id: Option<i32>,
+    ) -> Result<u64, Box<dyn Error>> {
+        let (from, to) = (from.to_string(), to.to_string());
+        self.with_tx(move |tx| {
+            let (from, to) = (from.clone(), to.clone());
+            Box::pin(async move {
+                let renamed: Vec<i32> = sqlx::query_scalar(
+                    r#"UPDATE questions
+                    SET tags = ARRAY(SELECT DISTINCT unnest(array_replace(tags, $1, $2)))
+                    WHERE $1 = ANY(tags) RETURNING id;"#,
+                )
+                .bind(&from)
+                .bind(&to)
+                .fetch_all(&mut **tx)
+                .await?;
+                for id in &renamed {
+                    let payload = serde_json::json!({ "renamed_tag": { "from": from, "to": to } });
+                    AppState::record_event(
+                        tx,
+                        "question",
+                        *id,
+                        EventAction::Update,
+                        actor_id,
+                        payload,
+                    )
+                    .await?;
+                }
+                Ok(renamed.len() as u64)
+            })
+        })
+        .await
+    }
+
+    /// Function to delete an account along with what it wrote, or handing what it wrote to the deleted user
+    ///
+    /// With `cascade` the account's questions and answers are deleted too, and the answers to those questions with them.
+    /// Otherwise they are kept and moved over to the `DELETED_USER_EMAIL` placeholder account.
+    /// Every question and answer deleted or moved is logged as an event, returns the number of accounts deleted
+    pub async fn delete_account(
+        self,
+        id: &AccountId,
+        cascade: bool,
+        actor_id: Option<i32>,
+    ) -> Result<u64, Box<dyn Error>> {
+        let id = id.0;
+        self.with_tx(move |tx| {
+            Box::pin(async move {
+                if cascade {
+                    // Replies go with the answers they reply to, so they are collected and logged too
+                    let answers: Vec<(i32, i32)> = sqlx::query_as(
+                        r#"WITH RECURSIVE doomed AS (
+                            SELECT id FROM answers WHERE author_id = $1
+                            OR corresponding_question IN (SELECT id FROM questions WHERE author_id = $1)
+                            UNION SELECT a.id FROM answers a JOIN doomed d ON a.parent_answer_id = d.id
+                        )
+                        DELETE FROM answers WHERE id IN (SELECT id FROM doomed)
+                        RETURNING id, corresponding_question;"#,
+                    )
+                    .bind(id)
+                    .fetch_all(&mut **tx)
+                    .await?;
+                    record_answer_deletes(tx, &answers, actor_id, "account").await?;
+                    let questions: Vec<i32> = sqlx::query_scalar(
+                        r#"DELETE FROM questions WHERE author_id = $1 RETURNING id;"#,
+                    )
+                    .bind(id)
+                    .fetch_all(&mut **tx)
+                    .await?;
+                    for question_id in questions {
+                        let payload = serde_json::json!({ "deleted_with": "account" });
+                        AppState::record_event(
+                            tx,
+                            "question",
+                            question_id,
+                            EventAction::Delete,
+                            actor_id,
+                            payload,
+                        )
+                        .await?;
+                    }
+                } else {
+                    for (table, entity_type) in [("answers", "answer"), ("questions", "question")] {
+                        let moved: Vec<i32> = sqlx::query_scalar(&format!(
+                            r#"UPDATE {} SET author_id = (SELECT id FROM accounts WHERE email = $2)
+                            WHERE author_id = $1 RETURNING id;"#,
+                            table
+                        ))
+                        .bind(id)
+                        .bind(DELETED_USER_EMAIL)
+                        .fetch_all(&mut **tx)
+                        .await?;
+                        for entity_id in moved {
+                            let payload = serde_json::json!({ "author": DELETED_USER_EMAIL });
+                            AppState::record_event(
+                                tx,
+                                entity_type,
+                                entity_id,
+                                EventAction::Update,
+                                actor_id,
+                                payload,
+                            )
+                            .await?;
+                        }
+                    }
+                }
+                let result = sqlx::query(r#"DELETE FROM accounts WHERE id = $1;"#)
+                    .bind(id)
+                    .execute(&mut **tx)
+                    .await?;
+                if result.rows_affected() > 0 {
+                    let payload = serde_json::json!({ "cascade": cascade });
+                    AppState::record_event(
+                        tx,
+                        "account",
+                        id,
+                        EventAction::Delete,
+                        actor_id,
+                        payload,
+                    )
+                    .await?;
+                }
+                Ok(result.rows_affected())
+            })
+        })
+        .await
+    }
+
+    /// Function to update an account, returning the number of accounts updated
+    ///
+    /// A display name left out of the update keeps the one the account already has, a blank one clears it
+    pub async fn update_account(
+        self,
+        id: &AccountId,
+        acc: Account,
+        actor_id: Option<i32>,
+    ) -> Result<u64, Box<dyn Error>> {
+        let id = id.0;
+        self.with_tx(move |tx| {
+            let acc = acc.clone();
+            Box::pin(async move {
+                let result = sqlx::query(
+                    r#"UPDATE accounts SET email = $1, password = $2, display_name = CASE WHEN $3::text IS NULL THEN display_name ELSE NULLIF(btrim($3), '') END
+                    WHERE id = $4;"#,
+                )
+                .bind(&acc.email)
+                .bind(acc.password)
+                .bind(&acc.display_name)
+                .bind(id)
+                .execute(&mut **tx)
+                .await?;
+                if result.rows_affected() > 0 {
+                    // The password is left out, the log is readable by every admin
+                    let payload = serde_json::json!({
+                        "email": acc.email,
+                        "display_name": acc.display_name,
+                    });
+                    AppState::record_event(tx, "account", id, EventAction::Update, actor_id, payload)
+                        .await?;
+                }
+                Ok(result.rows_affected())
+            })
+        })
+        .await
+    }
+}
+
+/// Function to record a delete event for each answer removed along with something else
+///
+/// `deleted` holds the id and question of each answer, `deleted_with` names what took them along
+async fn record_answer_deletes(
+    tx: &mut Transaction<'static, Postgres>,
+    deleted: &[(i32, i32)],
+    actor_id: Option<i32>,
+    deleted_with: &str,
+) -> Result<(), sqlx::Error> {
+    for (answer_id, question_id) in deleted {
+        let payload = serde_json::json!({
+            "question_id": question_id,
+            "deleted_with": deleted_with,
+        });
+        AppState::record_event(
+            tx,
+            "answer",
+            *answer_id,
+            EventAction::Delete,
+            actor_id,
+            payload,
+        )
+        .await?;
+    }
+    Ok(())
+}
+
+/// Function to insert a question and record its create event, returns the new question's id
+///
+/// Shared by `AppState::add_question` and `RequestTx::add_question` so both log the same event
+async fn insert_question(
+    tx: &mut Transaction<'static, Postgres>,
+    question: &Question,
+    author_id: Option<i32>,
+) -> Result<i32, sqlx::Error> {
+    let tags = tags_column(&question.tags);
+    let id: i32 = sqlx::query_scalar(
+        r#"INSERT INTO questions (title, content, tags, category, author_id) VALUES ($1, $2, $3, $4, $5)
+        RETURNING id;"#,
+    )
+    .bind(&question.title)
+    .bind(&question.content)
+    .bind(&tags)
+    .bind(question.category)
+    .bind(author_id)
+    .fetch_one(&mut **tx)
+    .await?;
+    let payload = serde_json::json!({
+        "title": question.title,
+        "content": question.content,
+        "tags": tags,
+        "category": question.category,
+    });
+    AppState::record_event(tx, "question", id, EventAction::Create, author_id, payload).await?;
+    Ok(id)
+}
+
+/// A request scoped transaction struct
+///
+/// For handlers with several steps that have to succeed or fail together. Begin one with
+/// `AppState::begin`, run each step on it and `commit` at the end. Dropping it without committing,
+/// as returning early on an error does, rolls every step back.
+/// Unlike `with_tx` nothing is retried, so lock what the steps depend on up front instead
+/// #Example:
+/// ```
+/// let mut tx = state.begin().await?;
+/// if tx.lock_question(&question_id).await? {
+///     tx.add_answer(&answer, None).await?;
+/// }
+/// tx.commit().await?;
+/// ```
+pub struct RequestTx(Transaction<'static, Postgres>);
+
+impl RequestTx {
+    /// Function to commit every step run on the transaction
+    pub async fn commit(self) -> Result<(), sqlx::Error> {
+        self.0.commit().await
+    }
+
+    /// Function to lock a question until the transaction ends, returns false if there is no such question
+    ///
+    /// Other transactions locking the same question wait for this one, so checks made on the
+    /// question's answers still hold when the transaction commits
+    pub async fn lock_question(&mut self, id: &QuestionId) -> Result<bool, sqlx::Error> {
+        let locked: Option<i32> =
+            sqlx::query_scalar(r#"SELECT id FROM questions WHERE id = $1 FOR UPDATE;"#)
+                .bind(id.0)
+                .fetch_optional(&mut *self.0)
+                .await?;
+        Ok(locked.is_some())
+    }
+
+    /// Function to check if a question was closed, a closed question takes no new answers
+    pub async fn question_closed(&mut self, question_id: &QuestionId) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar(r#"SELECT close_reason IS NOT NULL FROM questions WHERE id = $1;"#)
+            .bind(question_id.0)
+            .fetch_one(&mut *self.0)
+            .await
+    }
+
+    /// Function to check if a question already has `max` or more answers
+    ///
+    /// Only counts up to `max` rows, so this stays cheap for questions with a lot of answers
+    pub async fn answer_limit_reached(
+        &mut self,
+        question_id: &QuestionId,
+        max: i64,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"SELECT COUNT(*) >= $2 FROM
+            (SELECT 1 FROM answers WHERE corresponding_question = $1 LIMIT $2) AS capped;"#,
+        )
+        .bind(question_id.0)
+        .bind(max)
+        .fetch_one(&mut *self.0)
+        .await
+    }
+
+    /// Function to check if a question already has an answer with exactly this content
+    pub async fn has_duplicate_answer(
+        &mut self,
+        question_id: &QuestionId,
+        content: &str,
+    ) -> Result<bool, sqlx::Error> {
+        sqlx::query_scalar(
+            r#"SELECT EXISTS(SELECT 1 FROM answers WHERE corresponding_question = $1 AND content = $2);"#,
+        )
+        .bind(question_id.0)
+        .bind(content)
+        .fetch_one(&mut *self.0)
+        .await
+    }
+
+    /// Function to get the question an answer belongs to, or None if there is no such answer
+    pub async fn get_answer_question(
+        &mut self,
+        answer_id: &AnswerId,
+    ) -> Result<Option<QuestionId>, sqlx::Error> {
+        let question_id: Option<i32> =
+            sqlx::query_scalar(r#"SELECT corresponding_question FROM answers WHERE id = $1;"#)
+                .bind(answer_id.0)
+                .fetch_optional(&mut *self.0)
+                .await?;
+        Ok(question_id.map(QuestionId))
+    }
+
+    /// Function to get the account that asked a question, or None if it was asked anonymously
+    pub async fn question_author(&mut self, id: &QuestionId) -> Result<Option<i32>, sqlx::Error> {
+        sqlx::query_scalar(r#"SELECT author_id FROM questions WHERE id = $1;"#)
+            .bind(id.0)
+            .fetch_one(&mut *self.0)
+            .await
+    }
+
+    /// Function to mark an answer as the accepted answer of its question, replacing any earlier one
+    pub async fn accept_answer(
+        &mut self,
+        question_id: &QuestionId,
+        answer_id: &AnswerId,
+        actor_id: Option<i32>,
+    ) -> Result<(), sqlx::Error> {
+        sqlx::query(r#"UPDATE questions SET accepted_answer_id = $2 WHERE id = $1;"#)
+            .bind(question_id.0)
+            .bind(answer_id.0)
+            .execute(&mut *self.0)
             .await?;
-        Ok(tx.commit().await?)
+        let payload = serde_json::json!({ "accepted_answer_id": answer_id });
+        AppState::record_event(
+            &mut self.0,
+            "question",
+            question_id.0,
+            EventAction::Update,
+            actor_id,
+            payload,
+        )
+        .await
     }
 
-    pub async fn update_account(self, email: &str, acc: Account) -> Result<(), Box<dyn Error>> {
-        let tx = Pool::begin(&self.0).await?;
-        sqlx::query(r#"UPDATE accounts SET email = $1, password = $2 WHERE email = $3;"#)
-            .bind(acc.email)
-            .bind(acc.password)
-            .bind(email)
-            .execute(&self.0)
+    /// Function to add a question, recording the account that asked it if known, returns its id
+    pub async fn add_question(
+        &mut self,
+        question: &Question,
+        author_id: Option<i32>,
+    ) -> Result<QuestionId, sqlx::Error> {
+        Ok(QuestionId(
+            insert_question(&mut self.0, question, author_id).await?,
+        ))
+    }
+
+    /// Function to add an answer, recording the account that wrote it if known
+    ///
+    /// Returns the new answer as stored, with its id, rendered content and creation time
+    pub async fn add_answer(
+        &mut self,
+        answer: &Answer,
+        author_id: Option<i32>,
+    ) -> Result<Answer, sqlx::Error> {
+        let row = sqlx::query(&format!(
+            r#"INSERT INTO answers (corresponding_question, content, content_html, author_id, parent_answer_id)
+            VALUES ($1, $2, $3, $4, $5) RETURNING *, false AS is_accepted, {};"#,
+            author_name("answers.author_id")
+        ))
+        .bind(answer.question_id.0)
+        .bind(&answer.content)
+        .bind(markdown::render(&answer.content))
+        .bind(author_id)
+        .bind(answer.parent_answer_id.map(|parent| parent.0))
+        .fetch_one(&mut *self.0)
+        .await?;
+        // A new answer counts as activity on its question for the active sort
+        sqlx::query(r#"UPDATE questions SET last_activity_at = NOW() WHERE id = $1;"#)
+            .bind(answer.question_id.0)
+            .execute(&mut *self.0)
             .await?;
-        Ok(tx.commit().await?)
+        let id: i32 = row.try_get("id")?;
+        let answer = answer_from_row(&row)?;
+        let payload = serde_json::json!({
+            "question_id": answer.question_id,
+            "content": answer.content,
+            "parent_answer_id": answer.parent_answer_id,
+        });
+        AppState::record_event(
+            &mut self.0,
+            "answer",
+            id,
+            EventAction::Create,
+            author_id,
+            payload,
+        )
+        .await?;
+        Ok(answer)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn account_id_parses_positive_ids_only() {
+        assert_eq!("5".parse::<AccountId>().unwrap(), AccountId(5));
+        assert!("0".parse::<AccountId>().is_err());
+        assert!("-1".parse::<AccountId>().is_err());
+        assert!("x".parse::<AccountId>().is_err());
+    }
+
+    /// Function to build an account that passes validation, for the tests to break one field of
+    fn valid_account() -> Account {
+        Account {
+            id: AccountId(1),
+            email: "moes@pdx.edu".to_string(),
+            password: "correct9horse".to_string(),
+            display_name: Some("Nathan".to_string()),
+        }
+    }
+
+    /// Function to check that validation fails with a message mentioning `field`
+    fn rejects(account: Account, field: &str) {
+        match account.validate() {
+            Err(crate::api::ApiError::InvalidAccount(message)) => {
+                assert!(
+                    message.contains(field),
+                    "{} does not mention {}",
+                    message,
+                    field
+                )
+            }
+            other => panic!("expected InvalidAccount, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn validate_accepts_a_valid_account() {
+        assert!(valid_account().validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_an_email_without_at() {
+        rejects(
+            Account {
+                email: "moes.pdx.edu".to_string(),
+                ..valid_account()
+            },
+            "email",
+        );
+    }
+
+    #[test]
+    fn validate_rejects_a_short_password() {
+        let password = "a1".repeat(MIN_PASSWORD_LENGTH / 2 - 1);
+        rejects(
+            Account {
+                password,
+                ..valid_account()
+            },
+            "at least",
+        );
+    }
+
+    #[test]
+    fn validate_rejects_a_password_without_a_digit() {
+        rejects(
+            Account {
+                password: "correcthorse".to_string(),
+                ..valid_account()
+            },
+            "digit",
+        );
+    }
+
+    #[test]
+    fn validate_rejects_a_password_without_a_letter() {
+        rejects(
+            Account {
+                password: "1234567890".to_string(),
+                ..valid_account()
+            },
+            "letter",
+        );
+    }
+
+    #[test]
+    fn validate_rejects_a_common_password_in_any_case() {
+        rejects(
+            Account {
+                password: "PassWord123".to_string(),
+                ..valid_account()
+            },
+            "too common",
+        );
+    }
+
+    #[test]
+    fn validate_rejects_a_long_display_name() {
+        rejects(
+            Account {
+                display_name: Some("n".repeat(MAX_DISPLAY_NAME_LENGTH + 1)),
+                ..valid_account()
+            },
+            "display name",
+        );
     }
 }
@@ -0,0 +1,59 @@
+use crate::*;
+use axum::http::HeaderMap;
+use serde_json::{json, Value};
+
+/// The media type clients send in their Accept header to opt in to JSON:API responses
+pub const JSON_API_MEDIA_TYPE: &str = "application/vnd.api+json";
+
+/// Function to check if the client asked for a JSON:API response
+pub fn accepts_json_api(headers: &HeaderMap) -> bool {
+    web::accepts(headers, JSON_API_MEDIA_TYPE)
+}
+
+/// Function to wrap a serialized question in a JSON:API resource object
+///
+/// #Example:
+/// ```
+/// { "type": "question", "id": "1", "attributes": { "title": "...", "content": "..." } }
+/// ```
+fn to_resource(mut question: Value) -> Value {
+    let id = question
+        .as_object_mut()
+        .and_then(|question| question.remove("id"))
+        .map(|id| id.to_string())
+        .unwrap_or_default();
+    json!({
+        "type": "question",
+        "id": id,
+        "attributes": question,
+    })
+}
+
+/// Function to wrap a serialized question, or list of questions, in a JSON:API document
+pub fn to_document(questions: Value) -> Value {
+    let data = match questions {
+        Value::Array(questions) => Value::Array(questions.into_iter().map(to_resource).collect()),
+        question => to_resource(question),
+    };
+    json!({ "data": data })
+}
+
+/// Function to transform a successful JSON response of questions into a JSON:API document
+///
+/// The response is only transformed when the client sent `Accept: application/vnd.api+json`,
+/// everyone else gets the existing JSON unchanged.
+pub fn negotiate(headers: &HeaderMap, response: Response<String>) -> Response<String> {
+    if !response.status().is_success() || !accepts_json_api(headers) {
+        return response;
+    }
+    let (mut parts, body) = response.into_parts();
+    let questions: Value = match serde_json::from_str(&body) {
+        Ok(questions) => questions,
+        Err(_) => return Response::from_parts(parts, body),
+    };
+    parts
+        .headers
+        .insert(CONTENT_TYPE, HeaderValue::from_static(JSON_API_MEDIA_TYPE));
+    let body = serde_json::to_string_pretty(&to_document(questions)).unwrap();
+    Response::from_parts(parts, body)
+}
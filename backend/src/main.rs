@@ -1,9 +1,10 @@
-use axum::http::header::CONTENT_TYPE;
-use axum::http::HeaderValue;
+use axum::http::header::{AUTHORIZATION, CONTENT_TYPE};
+use axum::http::{HeaderMap, HeaderValue, Uri};
 use axum::routing::{delete, put};
 use axum::{
-    extract::{Json, Query, State},
+    extract::{FromRequestParts, Json, Path, Query, Request, State},
     http::{Method, StatusCode},
+    middleware::{self, Next},
     response::{IntoResponse, Response},
     routing::get,
     routing::post,
@@ -12,48 +13,416 @@ use axum::{
 use serde::{Deserialize, Serialize};
 use sqlx::{self, postgres::PgPool, Pool, Row};
 use std::error::Error;
+use std::net::SocketAddr;
 use std::str::FromStr;
-use std::time::Duration;
+use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
+use tokio::sync::watch;
 use tower_http::cors::CorsLayer;
 use tower_http::trace;
-use tower_sessions::{Expiry, MemoryStore, SessionManagerLayer};
+use tower_sessions::{Expiry, SessionManagerLayer};
+use tower_sessions_sqlx_store::PostgresStore;
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
 use utoipa::{OpenApi, ToSchema};
 extern crate thiserror;
+mod answer_stream;
 mod api;
 mod auth;
 mod bad_words_api;
+mod config;
 mod database;
+mod json_api;
+mod markdown;
 mod question;
+mod question_cache;
+mod rate_limit;
 mod web;
 use crate::api::{
-    delete_account, delete_answer, delete_question, get_account, get_answers, get_questions,
-    post_account, post_answer, post_question, put_account, put_answer, put_question,
+    delete_account, delete_answer, delete_question, get_account, get_account_by_id,
+    get_account_exists, get_answer_count, get_answer_stream, get_answers, get_applied_migrations,
+    get_events, get_feed, get_maintenance, get_me, get_question_history, get_question_with_answers,
+    get_questions, get_reports, get_search, get_tags, get_trending_questions,
+    get_unanswered_questions, json_error, post_accept_answer, post_account, post_answer,
+    post_answer_report, post_answer_vote, post_close_question, post_maintenance,
+    post_mark_duplicate, post_pin_question, post_promote_account, post_qa, post_question,
+    post_question_answer, post_rename_tag, post_unpin_question, put_account, put_answer,
+    put_question, search_accounts,
+};
+use crate::auth::{login, Claims};
+use crate::config::TrailingSlash;
+use crate::question::{
+    Category, CloseReason, NewQuestion, Question, QuestionFields, QuestionId, QuestionPreview,
+    QuestionRevision, QuestionSort, QuestionSummary, TagSort,
+};
+use crate::web::{
+    get_entry_point, get_livez, get_profanity_health, get_question, get_question_by_id, get_readyz,
 };
-use crate::auth::login;
-use crate::question::{Question, QuestionId};
-use crate::web::{get_entry_point, get_question};
 use database::AppState;
 use utoipa_rapidoc::RapiDoc;
 use utoipa_redoc::{Redoc, Servable};
 use utoipa_swagger_ui::SwaggerUi;
 
-/// API function to handle a not found error instead of other hard coding stuff. Will expand further in the future
-async fn handle_not_found() -> impl IntoResponse {
+/// Function to escape text for use inside HTML
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// API function to handle a not found error instead of other hard coding stuff
+///
+/// Browsers (Accept: text/html) get an HTML page, the NOT_FOUND_PAGE one if configured,
+/// everyone else gets the JSON error envelope with the path that was not found
+async fn handle_not_found(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    uri: Uri,
+) -> impl IntoResponse {
+    let path = uri.path();
+    if web::accepts(&headers, "text/html") {
+        let page = match &state.2.not_found_page {
+            Some(page) => page.clone(),
+            None => format!(
+                "<!DOCTYPE html>\n<html>\n<head><title>Not Found</title></head>\n<body>\n<h1>404 - Not Found</h1>\n<p>Nothing lives at {}.</p>\n<p><a href=\"/\">Back to the start</a></p>\n</body>\n</html>\n",
+                escape_html(path)
+            ),
+        };
+        return Response::builder()
+            .status(StatusCode::NOT_FOUND)
+            .header(CONTENT_TYPE, "text/html; charset=utf-8")
+            .body(page)
+            .unwrap();
+    }
+    let body = serde_json::json!({
+        "status": StatusCode::NOT_FOUND.as_u16(),
+        "error": "Not Found",
+        "code": "NOT_FOUND",
+        "path": path,
+    });
     Response::builder()
         .status(StatusCode::NOT_FOUND)
-        .body("Not Found".to_string())
+        .header(CONTENT_TYPE, "application/json")
+        .body(body.to_string())
         .unwrap()
 }
 
+/// Middleware to log a single access line per request with its method, path, status and duration
+///
+/// Only logs when ACCESS_LOG=1, otherwise the request is passed straight through
+async fn access_log(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> axum::response::Response {
+    if !state.2.access_log {
+        return next.run(request).await;
+    }
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let start = Instant::now();
+    let response = next.run(request).await;
+    tracing::info!(
+        target: "access",
+        %method,
+        %path,
+        status = response.status().as_u16(),
+        duration_ms = start.elapsed().as_millis() as u64,
+        "request"
+    );
+    response
+}
+
+/// Fields whose values never make it into the body log, matched case insensitively anywhere in the key
+const SENSITIVE_FIELDS: [&str; 3] = ["password", "secret", "token"];
+
+/// How many characters of a body the body log keeps
+const LOGGED_BODY_LENGTH: usize = 1024;
+
+/// Function to replace the values of sensitive fields in a JSON value, at any depth
+fn redact(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                let key = key.to_lowercase();
+                if SENSITIVE_FIELDS.iter().any(|field| key.contains(field)) {
+                    *value = serde_json::Value::String("[redacted]".to_string());
+                } else {
+                    redact(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(redact),
+        _ => (),
+    }
+}
+
+/// Function to turn a body into the text the body log shows, redacted and cut to `LOGGED_BODY_LENGTH`
+///
+/// Bodies that are not JSON can not be redacted field by field, so only their size is shown
+fn logged_body(bytes: &[u8]) -> String {
+    if bytes.is_empty() {
+        return String::new();
+    }
+    let text = match serde_json::from_slice::<serde_json::Value>(bytes) {
+        Ok(mut value) => {
+            redact(&mut value);
+            value.to_string()
+        }
+        Err(_) => return format!("[{} bytes, not JSON]", bytes.len()),
+    };
+    match text.char_indices().nth(LOGGED_BODY_LENGTH) {
+        Some((end, _)) => format!("{}…", &text[..end]),
+        None => text,
+    }
+}
+
+/// Middleware to log request and response bodies for local debugging, only when LOG_BODIES=1 in a debug build
+///
+/// Both bodies are buffered to be logged, apart from server sent event streams, which never end
+async fn log_bodies(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> axum::response::Response {
+    if !state.2.log_bodies {
+        return next.run(request).await;
+    }
+    let (parts, body) = request.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            return json_error(StatusCode::BAD_REQUEST, error).into_response();
+        }
+    };
+    tracing::info!(
+        target: "bodies",
+        method = %parts.method,
+        path = %parts.uri.path(),
+        body = %logged_body(&bytes),
+        "request body"
+    );
+    let response = next
+        .run(Request::from_parts(parts, axum::body::Body::from(bytes)))
+        .await;
+    let streaming = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .is_some_and(|value| value.starts_with("text/event-stream"));
+    if streaming {
+        return response;
+    }
+    let (parts, body) = response.into_parts();
+    let bytes = match axum::body::to_bytes(body, usize::MAX).await {
+        Ok(bytes) => bytes,
+        Err(error) => {
+            return json_error(StatusCode::INTERNAL_SERVER_ERROR, error).into_response();
+        }
+    };
+    tracing::info!(
+        target: "bodies",
+        status = parts.status.as_u16(),
+        body = %logged_body(&bytes),
+        "response body"
+    );
+    axum::response::Response::from_parts(parts, axum::body::Body::from(bytes))
+}
+
+/// Middleware to answer every request with a 503 while the service is in maintenance mode
+///
+/// Admins still get through so they can check on things, as do the probes and logging in
+async fn maintenance_mode(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> axum::response::Response {
+    if !state.4.load(Ordering::Relaxed) {
+        return next.run(request).await;
+    }
+    let path = request.uri().path();
+    if matches!(path, "/livez" | "/readyz" | "/health/profanity" | "/login") {
+        return next.run(request).await;
+    }
+    let (mut parts, body) = request.into_parts();
+    if let Ok(claims) = Claims::from_request_parts(&mut parts, &state).await {
+        if claims.is_admin {
+            return next.run(Request::from_parts(parts, body)).await;
+        }
+    }
+    json_error(
+        StatusCode::SERVICE_UNAVAILABLE,
+        "Down for maintenance, please try again later",
+    )
+    .into_response()
+}
+
+/// Middleware to turn away every POST, PUT, PATCH and DELETE with a 503 while READ_ONLY is set
+///
+/// Unlike maintenance mode reads keep working, as does logging in since that changes nothing
+async fn read_only_mode(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> axum::response::Response {
+    let mutating = matches!(
+        *request.method(),
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    );
+    if !state.2.read_only || !mutating || request.uri().path() == "/login" {
+        return next.run(request).await;
+    }
+    json_error(StatusCode::SERVICE_UNAVAILABLE, "Service is read-only").into_response()
+}
+
+/// Middleware to clear the cached question list after every POST, PUT, PATCH and DELETE
+///
+/// Questions change through their own routes but also through answers, accounts and tags,
+/// so any request that may have changed something clears it
+async fn invalidate_question_cache(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> axum::response::Response {
+    let mutating = matches!(
+        *request.method(),
+        Method::POST | Method::PUT | Method::PATCH | Method::DELETE
+    );
+    let response = next.run(request).await;
+    if mutating {
+        state.7.clear();
+    }
+    response
+}
+
+/// Paths of the API docs pages, these serve their own routes with a trailing slash
+const DOCS_PATHS: [&str; 3] = ["/swagger-ui", "/redoc", "/rapidoc"];
+
+/// Middleware to apply the TRAILING_SLASH policy to paths ending in a slash, before they are routed
+///
+/// The root and the API docs pages are left alone
+async fn trailing_slash(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> axum::response::Response {
+    let path = request.uri().path();
+    let docs = DOCS_PATHS.iter().any(|prefix| path.starts_with(prefix));
+    if path == "/" || !path.ends_with('/') || docs {
+        return next.run(request).await;
+    }
+    let trimmed = path.trim_end_matches('/');
+    let trimmed = if trimmed.is_empty() { "/" } else { trimmed };
+    let path_and_query = match request.uri().query() {
+        Some(query) => format!("{}?{}", trimmed, query),
+        None => trimmed.to_string(),
+    };
+    match state.2.trailing_slash {
+        TrailingSlash::Redirect => match HeaderValue::from_str(&path_and_query) {
+            Ok(location) => (
+                StatusCode::PERMANENT_REDIRECT,
+                [(axum::http::header::LOCATION, location)],
+            )
+                .into_response(),
+            Err(_) => json_error(StatusCode::BAD_REQUEST, "Invalid path").into_response(),
+        },
+        TrailingSlash::Strip => {
+            let (mut parts, body) = request.into_parts();
+            let mut uri = parts.uri.into_parts();
+            uri.path_and_query = path_and_query.parse().ok();
+            parts.uri = match Uri::from_parts(uri) {
+                Ok(uri) => uri,
+                Err(_) => {
+                    return json_error(StatusCode::BAD_REQUEST, "Invalid path").into_response()
+                }
+            };
+            next.run(Request::from_parts(parts, body)).await
+        }
+    }
+}
+
+/// Middleware to add the security headers, the configured Content-Security-Policy and `X-Content-Type-Options: nosniff`
+///
+/// The API docs pages load their scripts from CDNs, so they are left without a policy
+async fn security_headers(
+    State(state): State<AppState>,
+    request: Request,
+    next: Next,
+) -> axum::response::Response {
+    let path = request.uri().path();
+    let docs = DOCS_PATHS.iter().any(|prefix| path.starts_with(prefix));
+    let mut response = next.run(request).await;
+    let headers = response.headers_mut();
+    headers.insert(
+        "X-Content-Type-Options",
+        HeaderValue::from_static("nosniff"),
+    );
+    if let Some(policy) = state.2.content_security_policy.as_ref().filter(|_| !docs) {
+        match HeaderValue::from_str(policy) {
+            Ok(policy) => {
+                headers.insert("Content-Security-Policy", policy);
+            }
+            Err(error) => tracing::warn!("invalid CONTENT_SECURITY_POLICY: {}", error),
+        }
+    }
+    response
+}
+
+/// Function to wait for ctrl-c or SIGTERM, then tell the background tasks to stop
+///
+/// Used as the graceful shutdown signal of the server
+async fn shutdown_signal(shutdown: watch::Sender<bool>) {
+    let ctrl_c = async {
+        tokio::signal::ctrl_c()
+            .await
+            .expect("failed to listen for ctrl-c");
+    };
+    #[cfg(unix)]
+    let terminate = async {
+        tokio::signal::unix::signal(tokio::signal::unix::SignalKind::terminate())
+            .expect("failed to listen for SIGTERM")
+            .recv()
+            .await;
+    };
+    #[cfg(not(unix))]
+    let terminate = std::future::pending::<()>();
+    tokio::select! {
+        _ = ctrl_c => {},
+        _ = terminate => {},
+    }
+    tracing::info!("shutting down");
+    let _ = shutdown.send(true);
+}
+
+/// Background task purging expired sessions every `every`, until shutdown is signalled
+async fn purge_sessions_periodically(
+    state: AppState,
+    every: Duration,
+    mut shutdown: watch::Receiver<bool>,
+) {
+    let mut interval = tokio::time::interval(every);
+    loop {
+        tokio::select! {
+            _ = interval.tick() => match state.purge_expired_sessions().await {
+                Ok(purged) => tracing::info!("purged {} expired sessions", purged),
+                Err(error) => tracing::error!("failed to purge expired sessions: {}", error),
+            },
+            _ = shutdown.changed() => break,
+        }
+    }
+}
+
 #[tokio::main]
 async fn main() {
+    // LOG_FORMAT=json is for log aggregators, anything else keeps the human readable output
+    let json_logs = config::env_or("LOG_FORMAT", "pretty".to_string()) == "json";
     tracing_subscriber::registry()
         .with(
             tracing_subscriber::EnvFilter::try_from_default_env()
                 .unwrap_or_else(|_| "questions=debug,info".into()),
         )
-        .with(tracing_subscriber::fmt::layer())
+        .with(json_logs.then(|| tracing_subscriber::fmt::layer().json()))
+        .with((!json_logs).then(tracing_subscriber::fmt::layer))
         .init();
     // https://carlosmv.hashnode.dev/adding-logging-and-tracing-to-an-axum-app-rust
     // Credit to course knock-knock for the trace layer
@@ -63,50 +432,167 @@ async fn main() {
     let cors = CorsLayer::new()
         .allow_origin("http://localhost:8080".parse::<HeaderValue>().unwrap())
         .allow_methods([Method::GET, Method::POST, Method::PUT, Method::DELETE])
-        .allow_headers([CONTENT_TYPE])
+        .allow_headers([CONTENT_TYPE, AUTHORIZATION])
         .allow_credentials(true)
         .max_age(Duration::from_secs(60) * 10); // 10 minutes, was just toying with cors
     let swagger_ui =
         SwaggerUi::new("/swagger-ui").url("/api-docs/openapi.json", api::ApiDoc::openapi());
     let redoc_ui = Redoc::with_url("/redoc", api::ApiDoc::openapi());
     let rapidoc_ui = RapiDoc::new("/api-docs/openapi.json").path("/rapidoc");
-    let session_store = MemoryStore::default();
+    let state = AppState::new().await.unwrap();
+    let session_store = PostgresStore::new(state.0.clone());
+    session_store.migrate().await.unwrap();
     let session_layer = SessionManagerLayer::new(session_store)
         .with_secure(false)
         .with_expiry(Expiry::OnSessionEnd);
-    let state = AppState::new().await.unwrap();
+    let (shutdown_tx, shutdown_rx) = watch::channel(false);
+    let session_cleanup = state.2.session_cleanup_secs.map(|secs| {
+        tokio::spawn(purge_sessions_periodically(
+            state.clone(),
+            Duration::from_secs(secs),
+            shutdown_rx,
+        ))
+    });
     let app = Router::new()
         .route("/", get(get_entry_point))
+        // Probes for the orchestrator, kept unauthenticated and cheap
+        .route("/livez", get(get_livez))
+        .route("/readyz", get(get_readyz))
+        .route("/health/profanity", get(get_profanity_health))
         .route("/questions", get(get_questions))
         .route("/questions", post(post_question))
+        .route("/questions/unanswered", get(get_unanswered_questions))
+        .route("/questions/trending", get(get_trending_questions))
+        .route("/feed.xml", get(get_feed))
+        .route("/search", get(get_search))
+        .route("/questions/:id", get(get_question_by_id))
+        .route("/questions/:id/history", get(get_question_history))
+        .route("/questions/:id/pin", post(post_pin_question))
+        .route("/questions/:id/unpin", post(post_unpin_question))
+        .route("/questions/:id/mark-duplicate", post(post_mark_duplicate))
+        .route("/questions/:id/close", post(post_close_question))
         .route("/question", get(get_question))
+        .route("/question/answers", get(get_question_with_answers))
         .route("/questions", put(put_question))
         .route("/questions", delete(delete_question))
         // The following routes are for the answers portion of the API
         .route("/answers", post(post_answer))
+        .route("/questions/:id/answers", post(post_question_answer))
+        .route("/qa", post(post_qa))
         .route("/answers", delete(delete_answer))
         .route("/answers", put(put_answer))
         .route("/answers", get(get_answers))
+        .route("/answers/count", get(get_answer_count))
+        .route("/questions/:id/answers/stream", get(get_answer_stream))
+        .route("/answers/:id/vote", post(post_answer_vote))
+        .route("/answers/:id/accept", post(post_accept_answer))
+        .route("/answers/:id/report", post(post_answer_report))
         // The following routes are for the accounts portion of the API
         .route("/accounts", post(post_account))
         .route("/accounts", delete(delete_account))
         .route("/accounts", put(put_account))
         .route("/accounts", get(get_account))
+        .route("/accounts/search", get(search_accounts))
+        .route("/accounts/exists", get(get_account_exists))
+        .route("/accounts/:id", get(get_account_by_id))
+        .route("/accounts/:id/promote", post(post_promote_account))
+        .route("/me", get(get_me))
+        // Operations
+        .route("/admin/migrations", get(get_applied_migrations))
+        .route("/admin/reports", get(get_reports))
+        .route("/admin/events", get(get_events))
+        .route("/tags", get(get_tags))
+        .route("/tags/rename", post(post_rename_tag))
+        .route(
+            "/admin/maintenance",
+            get(get_maintenance).post(post_maintenance),
+        )
         // auth stuffs
-        .route("/login", get(login))
+        .route("/login", get(login).post(login))
         // Layers
         .merge(swagger_ui)
         .merge(redoc_ui)
         .merge(rapidoc_ui)
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            invalidate_question_cache,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            read_only_mode,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            maintenance_mode,
+        ))
+        .layer(middleware::from_fn_with_state(
+            state.clone(),
+            security_headers,
+        ))
         .layer(cors)
         .layer(trace_layer)
+        .layer(middleware::from_fn_with_state(state.clone(), access_log))
+        .layer(middleware::from_fn_with_state(state.clone(), log_bodies))
         .layer(session_layer)
-        .with_state(state)
-        .fallback(handle_not_found);
+        .fallback(handle_not_found)
+        .with_state(state.clone());
+    // Routing happens before a router's own layers run, so the trailing slash is handled by an outer router
+    let app = Router::new()
+        .fallback_service(app)
+        .layer(middleware::from_fn_with_state(state, trailing_slash));
 
     let listener = tokio::net::TcpListener::bind("127.0.0.1:8000")
         .await
         .unwrap();
     tracing::debug!("serving {}", listener.local_addr().unwrap());
-    axum::serve(listener, app).await.unwrap();
+    // The peer address is what anonymous question posts are rate limited on
+    axum::serve(
+        listener,
+        app.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .with_graceful_shutdown(shutdown_signal(shutdown_tx))
+    .await
+    .unwrap();
+    if let Some(session_cleanup) = session_cleanup {
+        let _ = session_cleanup.await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redact_hides_sensitive_fields_at_any_depth() {
+        let mut value = serde_json::json!({
+            "account": { "Password": "hunter2", "email": "moes@pdx.edu" },
+            "client_secret": "shh",
+            "sessions": [{ "token": "abc", "id": 1 }]
+        });
+        redact(&mut value);
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "account": { "Password": "[redacted]", "email": "moes@pdx.edu" },
+                "client_secret": "[redacted]",
+                "sessions": [{ "token": "[redacted]", "id": 1 }]
+            })
+        );
+    }
+
+    #[test]
+    fn logged_body_only_shows_the_size_of_non_json() {
+        assert_eq!(
+            logged_body(b"email=moes&password=hunter2"),
+            "[27 bytes, not JSON]"
+        );
+    }
+
+    #[test]
+    fn logged_body_cuts_multibyte_text_on_a_char_boundary() {
+        let body = serde_json::Value::String("é".repeat(LOGGED_BODY_LENGTH * 2)).to_string();
+        let logged = logged_body(body.as_bytes());
+        // The opening quote is the first of the characters kept
+        assert_eq!(logged, format!("\"{}…", "é".repeat(LOGGED_BODY_LENGTH - 1)));
+    }
 }
@@ -0,0 +1,18 @@
+use pulldown_cmark::{html, Options, Parser};
+
+/// Function to render Markdown to HTML that is safe to put straight into a page
+///
+/// The HTML is sanitized with ammonia, so scripts, event handlers and the like never make it through
+/// #Example:
+/// ```
+/// assert_eq!(render("**bold**"), "<p><strong>bold</strong></p>\n");
+/// ```
+pub fn render(markdown: &str) -> String {
+    let parser = Parser::new_ext(
+        markdown,
+        Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH,
+    );
+    let mut unsafe_html = String::new();
+    html::push_html(&mut unsafe_html, parser);
+    ammonia::clean(&unsafe_html)
+}
@@ -1,42 +1,261 @@
+use crate::api::ApiError;
 use crate::*;
 use std::collections::HashSet;
 
 /// A question id struct
 ///
 /// This struct is used to represent the id of a question. Why, because the book said so, that's why.
+/// It is (de)serialized as the bare integer, which is what the frontend `Question { id: u32 }` expects.
 /// ##Example:
 /// ```
 /// {
-/// "id": "1"
+/// "id": 1
 /// }
 /// ```
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
+#[serde(transparent)]
 pub struct QuestionId(pub i32);
 
+/// A question category enum
+///
+/// A structured category for questions, on top of the free-form tags. Stored as lowercase text.
+/// ##Example:
+/// ```
+/// "debugging"
+/// ```
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, Hash, ToSchema, sqlx::Type,
+)]
+#[serde(rename_all = "lowercase")]
+#[sqlx(type_name = "text", rename_all = "lowercase")]
+pub enum Category {
+    #[default]
+    General,
+    Debugging,
+    Concept,
+    Tooling,
+}
+
+/// A close reason enum
+///
+/// Why a moderator closed a question, stored as lowercase text. A closed question takes no new answers
+/// ##Example:
+/// ```
+/// "off-topic"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Hash, ToSchema, sqlx::Type)]
+#[serde(rename_all = "kebab-case")]
+#[sqlx(type_name = "text", rename_all = "kebab-case")]
+pub enum CloseReason {
+    Duplicate,
+    OffTopic,
+    Unclear,
+    Resolved,
+}
+
+/// A question sort enum
+///
+/// How a page of questions is ordered, by id (the default) or by latest activity, newest first.
+/// Activity is the question being asked, edited or answered.
+/// ##Example:
+/// ```
+/// "active"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum QuestionSort {
+    #[default]
+    Id,
+    Active,
+}
+
+/// A question fields enum
+///
+/// Which fields a page of the question list carries: every field (the default) or a summary
+/// of the id, title, tags and answer count for light list views
+/// ##Example:
+/// ```
+/// "summary"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum QuestionFields {
+    #[default]
+    Full,
+    Summary,
+}
+
+/// A tag sort enum
+///
+/// How the tag list is ordered, most used first (the default) or alphabetically
+/// ##Example:
+/// ```
+/// "alpha"
+/// ```
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TagSort {
+    #[default]
+    Frequency,
+    Alpha,
+}
+
 /// A question struct
 ///
 /// This struct represents a question that can be asked and (future) answered via the API
 /// ##Example:
 /// ```
 /// {
-///    "id": "1",
+///    "id": 1,
 ///    "title": "What is cargo toml?",
 ///    "content": "I want to know what toml is and how it relates to cargo. Can someone explain?",
-///    "tags": ["rust", "toml", "cargo"]
+///    "tags": ["rust", "toml", "cargo"],
+///    "category": "tooling",
+///    "pinned": false,
+///    "answer_count": 2,
+///    "has_accepted_answer": true,
+///    "duplicate_of": 3,
+///    "close_reason": "duplicate"
 /// }
 /// ```
 ///
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
 pub struct Question {
-    #[schema(example = "1")]
+    #[schema(example = 1, value_type = i32)]
+    pub id: QuestionId,
+    #[schema(example = "What is rust?")]
+    pub title: String,
+    #[schema(example = "I want to know what rust is, can someone tell me?")]
+    pub content: String,
+    #[schema(example = "rust, programming, beginner")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<HashSet<String>>,
+    #[serde(default)]
+    pub category: Category,
+    /// Pinned questions are listed before all others, only admins can pin
+    #[serde(default, skip_deserializing)]
+    pub pinned: bool,
+    /// How many answers the question has, filled in by the question lists and when getting a single question
+    #[schema(example = 2)]
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub answer_count: Option<i64>,
+    /// Whether one of the answers was accepted, filled in next to the answer count.
+    /// The question list only keeps it with `?rich=true`
+    #[schema(example = true)]
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub has_accepted_answer: Option<bool>,
+    /// The question this one was marked a duplicate of by an admin, if any
+    #[schema(example = 3, value_type = Option<i32>)]
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<QuestionId>,
+    /// Why a moderator closed the question, None while it is open
+    #[schema(example = "resolved")]
+    #[serde(default, skip_deserializing, skip_serializing_if = "Option::is_none")]
+    pub close_reason: Option<CloseReason>,
+}
+
+/// A new question struct
+///
+/// This struct represents the fields of a question that can be sent when asking it via the API.
+/// Unknown fields are rejected, including the ones a question only has in responses such as `answer_count`
+/// ##Example:
+/// ```
+/// {
+///    "title": "What is cargo toml?",
+///    "content": "I want to know what toml is and how it relates to cargo. Can someone explain?",
+///    "tags": ["rust", "toml", "cargo"],
+///    "category": "tooling"
+/// }
+/// ```
+///
+#[derive(Debug, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NewQuestion {
+    /// Ignored, the database gives the question its id
+    #[schema(example = 0, value_type = Option<i32>)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<QuestionId>,
+    #[schema(example = "What is rust?")]
+    pub title: String,
+    #[schema(example = "I want to know what rust is, can someone tell me?")]
+    pub content: String,
+    #[schema(example = "rust, programming, beginner")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tags: Option<HashSet<String>>,
+    #[serde(default)]
+    pub category: Category,
+}
+
+impl From<NewQuestion> for Question {
+    fn from(question: NewQuestion) -> Self {
+        Question {
+            id: question.id.unwrap_or(QuestionId(0)),
+            title: question.title,
+            content: question.content,
+            tags: question.tags,
+            category: question.category,
+            pinned: false,
+            answer_count: None,
+            has_accepted_answer: None,
+            duplicate_of: None,
+            close_reason: None,
+        }
+    }
+}
+
+/// A question summary struct
+///
+/// This struct represents a question in a light list view, everything but its content and details left out
+/// ##Example:
+/// ```
+/// {
+///    "id": 1,
+///    "title": "What is cargo toml?",
+///    "tags": ["rust", "toml", "cargo"],
+///    "answer_count": 2
+/// }
+/// ```
+///
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct QuestionSummary {
+    #[schema(example = 1, value_type = i32)]
     pub id: QuestionId,
     #[schema(example = "What is rust?")]
     pub title: String,
+    #[schema(example = "rust, programming, beginner")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tags: Option<HashSet<String>>,
+    #[schema(example = 2)]
+    pub answer_count: i64,
+}
+
+/// A question revision struct
+///
+/// This struct represents the state of a question before one of its edits
+/// ##Example:
+/// ```
+/// {
+///    "question_id": 1,
+///    "title": "What is cargo toml?",
+///    "content": "I want to know what toml is.",
+///    "tags": ["toml"],
+///    "edited_at": "2024-05-01T12:00:00"
+/// }
+/// ```
+///
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, ToSchema)]
+pub struct QuestionRevision {
+    #[schema(example = 1, value_type = i32)]
+    pub question_id: QuestionId,
+    #[schema(example = "What is rust?")]
+    pub title: String,
     #[schema(example = "I want to know what rust is, can someone tell me?")]
     pub content: String,
     #[schema(example = "rust, programming, beginner")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<HashSet<String>>,
+    #[schema(value_type = String, example = "2024-05-01T12:00:00")]
+    pub edited_at: chrono::NaiveDateTime,
 }
 
 /// An update question struct
@@ -45,7 +264,7 @@ pub struct Question {
 /// ##Example:
 /// ```
 /// {
-///    "id": "1",
+///    "id": 1,
 ///    "title": "What is cargo toml?",
 ///    "content": "I want to know what toml is and how it relates to cargo. Can someone explain?",
 ///    "tags": ["rust", "toml", "cargo"]
@@ -53,8 +272,9 @@ pub struct Question {
 /// ```
 ///
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq, ToSchema)]
+#[serde(deny_unknown_fields)]
 pub struct UpdateQuestion {
-    #[schema(example = "1")]
+    #[schema(example = 1, value_type = Option<i32>)]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<QuestionId>,
     #[schema(example = "What is rust?")]
@@ -64,6 +284,8 @@ pub struct UpdateQuestion {
     #[schema(example = "rust, programming, beginner")]
     #[serde(skip_serializing_if = "Option::is_none")]
     pub tags: Option<HashSet<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub category: Option<Category>,
 }
 
 impl FromStr for QuestionId {
@@ -80,6 +302,80 @@ impl FromStr for QuestionId {
     }
 }
 
+/// How many characters of content a question preview keeps
+pub const PREVIEW_LENGTH: usize = 200;
+
+/// A question preview struct, a question whose content may have been cut short for a list
+///
+/// ##Example:
+/// ```
+/// {
+/// "id": 1,
+/// "title": "What is rust?",
+/// "content": "I want to know what rust is, can someone…",
+/// "category": "general",
+/// "truncated": true
+/// }
+/// ```
+#[derive(Debug, Serialize, ToSchema)]
+pub struct QuestionPreview {
+    #[serde(flatten)]
+    pub question: Question,
+    /// Whether the content was cut short
+    pub truncated: bool,
+}
+
+impl From<Question> for QuestionPreview {
+    /// Cuts the content to its first `PREVIEW_LENGTH` characters, never in the middle of a UTF-8 char
+    fn from(question: Question) -> Self {
+        if question.content.chars().count() <= PREVIEW_LENGTH {
+            return QuestionPreview {
+                question,
+                truncated: false,
+            };
+        }
+        let mut content: String = question.content.chars().take(PREVIEW_LENGTH).collect();
+        content.push('…');
+        QuestionPreview {
+            question: Question {
+                content,
+                ..question
+            },
+            truncated: true,
+        }
+    }
+}
+
+/// Most tags a single question may carry
+pub const MAX_TAGS: usize = 10;
+
+/// Longest a single tag may be, in characters
+pub const MAX_TAG_LENGTH: usize = 30;
+
+/// Function to check the tags of a question against the tag limits
+///
+/// #Errors:
+/// Returns `ApiError::InvalidTags` when there are more than `MAX_TAGS` tags or a tag is longer than `MAX_TAG_LENGTH`
+pub fn validate_tags(tags: &Option<HashSet<String>>) -> Result<(), ApiError> {
+    let tags = match tags {
+        Some(tags) => tags,
+        None => return Ok(()),
+    };
+    if tags.len() > MAX_TAGS {
+        return Err(ApiError::InvalidTags(format!(
+            "at most {} tags are allowed",
+            MAX_TAGS
+        )));
+    }
+    if let Some(tag) = tags.iter().find(|tag| tag.chars().count() > MAX_TAG_LENGTH) {
+        return Err(ApiError::InvalidTags(format!(
+            "tag \"{}\" is longer than {} characters",
+            tag, MAX_TAG_LENGTH
+        )));
+    }
+    Ok(())
+}
+
 // Credit to knock knock for the format_tags function
 pub fn format_tags(tags: &HashSet<String>) -> String {
     let taglist: Vec<&str> = tags.iter().map(String::as_ref).collect();
@@ -94,7 +390,10 @@ impl From<&Question> for String {
         text += &format!("Title: {}\n", question.title);
         text += &format!("Content: {}\n", question.content);
 
-        let mut annotations: Vec<String> = vec![format!("id: {}", question.id.0)];
+        let mut annotations: Vec<String> = vec![
+            format!("id: {}", question.id.0),
+            format!("category: {:?}", question.category),
+        ];
         if let Some(tags) = &question.tags {
             annotations.push(format!("tags: {:?}", format_tags(tags)));
         }
@@ -112,6 +411,51 @@ impl Clone for Question {
             title: self.title.clone(),
             content: self.content.clone(),
             tags: self.tags.clone(),
+            category: self.category,
+            pinned: self.pinned,
+            answer_count: self.answer_count,
+            has_accepted_answer: self.has_accepted_answer,
+            duplicate_of: self.duplicate_of.clone(),
+            close_reason: self.close_reason,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Function to build a question with the given content, everything else left at its default
+    fn question_with_content(content: String) -> Question {
+        Question::from(NewQuestion {
+            id: Some(QuestionId(1)),
+            title: "What is rust?".to_string(),
+            content,
+            tags: None,
+            category: Category::default(),
+        })
+    }
+
+    #[test]
+    fn preview_cuts_on_a_char_boundary_inside_multibyte_content() {
+        // One ASCII char first, so byte PREVIEW_LENGTH lands in the middle of a two byte 'é'
+        let content = format!("a{}", "é".repeat(PREVIEW_LENGTH + 50));
+        assert!(!content.is_char_boundary(PREVIEW_LENGTH));
+        let preview = QuestionPreview::from(question_with_content(content));
+        assert!(preview.truncated);
+        assert!(preview.question.content.ends_with('…'));
+        assert_eq!(preview.question.content.chars().count(), PREVIEW_LENGTH + 1);
+        assert_eq!(
+            preview.question.content,
+            format!("a{}…", "é".repeat(PREVIEW_LENGTH - 1))
+        );
+    }
+
+    #[test]
+    fn preview_keeps_content_of_exactly_preview_length() {
+        let content = "é".repeat(PREVIEW_LENGTH);
+        let preview = QuestionPreview::from(question_with_content(content.clone()));
+        assert!(!preview.truncated);
+        assert_eq!(preview.question.content, content);
+    }
+}
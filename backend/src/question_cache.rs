@@ -0,0 +1,108 @@
+use crate::question::Question;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// A cached page of questions along with the total the page was cut from
+pub type CachedPage = (Vec<Question>, i64);
+
+/// Question list cache struct
+///
+/// This struct keeps pages of the question list for `ttl`, keyed by the paging and filter parameters
+/// that picked them, so list loads close together share one trip to the database.
+/// It is cleared whenever a request changes something, a zero `ttl` turns it off.
+/// Each clear bumps the generation, so a page read before a clear is not kept after it.
+/// ##Example:
+/// ```
+/// let cache = QuestionCache::new(Duration::from_secs(5));
+/// if let Some((questions, total)) = cache.get(&key) {
+///     // serve the cached page
+/// }
+/// let generation = cache.generation();
+/// // read the page from the database
+/// cache.insert(key, page, generation);
+/// ```
+#[derive(Debug)]
+pub struct QuestionCache {
+    ttl: Duration,
+    pages: Mutex<HashMap<String, (Instant, CachedPage)>>,
+    generation: AtomicU64,
+}
+
+impl QuestionCache {
+    /// Function to create a cache keeping each page for `ttl`
+    pub fn new(ttl: Duration) -> Self {
+        QuestionCache {
+            ttl,
+            pages: Mutex::new(HashMap::new()),
+            generation: AtomicU64::new(0),
+        }
+    }
+
+    /// Function to get a page still within its ttl
+    pub fn get(&self, key: &str) -> Option<CachedPage> {
+        let pages = self.pages.lock().unwrap();
+        pages
+            .get(key)
+            .filter(|(cached_at, _)| cached_at.elapsed() < self.ttl)
+            .map(|(_, page)| page.clone())
+    }
+
+    /// Function to get the current generation, taken before reading a page that is to be inserted
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Function to keep a page read at `generation`, dropping the pages that are past their ttl on the way
+    ///
+    /// The page is left out if the cache was cleared since, it may have been read before the change committed
+    pub fn insert(&self, key: String, page: CachedPage, generation: u64) {
+        if self.ttl.is_zero() {
+            return;
+        }
+        let mut pages = self.pages.lock().unwrap();
+        if self.generation.load(Ordering::SeqCst) != generation {
+            return;
+        }
+        pages.retain(|_, (cached_at, _)| cached_at.elapsed() < self.ttl);
+        pages.insert(key, (Instant::now(), page));
+    }
+
+    /// Function to drop every page, called after anything that may have changed the questions
+    pub fn clear(&self) {
+        let mut pages = self.pages.lock().unwrap();
+        pages.clear();
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn insert_keeps_a_page_read_at_the_current_generation() {
+        let cache = QuestionCache::new(Duration::from_secs(60));
+        let generation = cache.generation();
+        cache.insert("page".to_string(), (Vec::new(), 3), generation);
+        assert_eq!(cache.get("page"), Some((Vec::new(), 3)));
+    }
+
+    #[test]
+    fn insert_drops_a_page_read_before_a_clear() {
+        let cache = QuestionCache::new(Duration::from_secs(60));
+        let generation = cache.generation();
+        cache.clear();
+        cache.insert("page".to_string(), (Vec::new(), 3), generation);
+        assert_eq!(cache.get("page"), None);
+    }
+
+    #[test]
+    fn zero_ttl_never_stores() {
+        let cache = QuestionCache::new(Duration::ZERO);
+        let generation = cache.generation();
+        cache.insert("page".to_string(), (Vec::new(), 3), generation);
+        assert_eq!(cache.get("page"), None);
+    }
+}
@@ -0,0 +1,58 @@
+use std::collections::{HashMap, VecDeque};
+use std::net::IpAddr;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Per IP rate limiter struct
+///
+/// This struct lets each IP address make at most `max` requests in any `window`, a sliding window
+/// over the times of its latest requests. Used on anonymous question posts, which no login holds back.
+/// #Example:
+/// ```
+/// let limiter = IpRateLimiter::new(3, Duration::from_secs(60 * 60));
+/// if let Err(retry_after) = limiter.check(addr.ip()) {
+///     // turn the request away for retry_after
+/// }
+/// ```
+#[derive(Debug)]
+pub struct IpRateLimiter {
+    max: usize,
+    window: Duration,
+    hits: Mutex<HashMap<IpAddr, VecDeque<Instant>>>,
+}
+
+impl IpRateLimiter {
+    /// Function to create a limiter allowing `max` requests per IP in every `window`
+    pub fn new(max: usize, window: Duration) -> Self {
+        IpRateLimiter {
+            max,
+            window,
+            hits: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Function to count a request from an IP, if it is still within the limit
+    ///
+    /// Returns how long until the IP may try again when it is over the limit, that request is not counted
+    pub fn check(&self, ip: IpAddr) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut hits = self.hits.lock().unwrap();
+        if !hits.contains_key(&ip) {
+            // Forget the IPs whose requests have all left the window, so the map does not keep every IP ever seen
+            hits.retain(|_, times| times.back().is_some_and(|last| now - *last < self.window));
+        }
+        let times = hits.entry(ip).or_default();
+        while times
+            .front()
+            .is_some_and(|first| now - *first >= self.window)
+        {
+            times.pop_front();
+        }
+        if times.len() >= self.max {
+            let first = times.front().copied().unwrap_or(now);
+            return Err(self.window - (now - first));
+        }
+        times.push_back(now);
+        Ok(())
+    }
+}
@@ -1,23 +1,51 @@
 use crate::api::{ApiError, IdParam};
 use crate::*;
+use axum::http::{header::ACCEPT, HeaderMap};
+
+/// Function to check if the client listed the given media type in its Accept header
+pub fn accepts(headers: &HeaderMap, media_type: &str) -> bool {
+    headers
+        .get(ACCEPT)
+        .and_then(|accept| accept.to_str().ok())
+        .map(|accept| {
+            accept
+                .split(',')
+                .any(|media| media.trim().starts_with(media_type))
+        })
+        .unwrap_or(false)
+}
+
+/// Function to turn a failed database call into a 500, so it is not mistaken for a missing question
+fn database_error(error: Box<dyn Error>) -> Response<String> {
+    tracing::event!(tracing::Level::ERROR, "{:?}", error);
+    Response::builder()
+        .status(StatusCode::INTERNAL_SERVER_ERROR)
+        .body(ApiError::DatabaseError(error.to_string()).to_string())
+        .unwrap()
+}
 
 /// Web function to get a single question from the questions
 pub async fn get_question(
     State(state): State<AppState>,
+    headers: HeaderMap,
     Query(IdParam { id }): Query<IdParam>,
 ) -> impl IntoResponse {
     match id {
         Some(id) => {
             let question_id = QuestionId(id);
             match state.get_question(&question_id).await {
-                Ok(question) => Response::builder()
-                    .status(StatusCode::OK)
-                    .body(serde_json::to_string_pretty(&question).unwrap())
-                    .unwrap(),
-                Err(_) => Response::builder()
+                Ok(Some(question)) => json_api::negotiate(
+                    &headers,
+                    Response::builder()
+                        .status(StatusCode::OK)
+                        .body(serde_json::to_string_pretty(&question).unwrap())
+                        .unwrap(),
+                ),
+                Ok(None) => Response::builder()
                     .status(StatusCode::NOT_FOUND)
                     .body(ApiError::QuestionNotFound.to_string())
                     .unwrap(),
+                Err(error) => database_error(error),
             }
         }
         None => Response::builder()
@@ -27,6 +55,40 @@ pub async fn get_question(
     }
 }
 
+/// Web function to get a single question by the id in the path
+///
+/// Clients sending `Accept: text/plain` get the text form of the question instead of JSON
+pub async fn get_question_by_id(
+    State(state): State<AppState>,
+    headers: HeaderMap,
+    Path(id): Path<i32>,
+) -> impl IntoResponse {
+    let question = match state.get_question(&QuestionId(id)).await {
+        Ok(Some(question)) => question,
+        Ok(None) => {
+            return Response::builder()
+                .status(StatusCode::NOT_FOUND)
+                .body(ApiError::QuestionNotFound.to_string())
+                .unwrap()
+        }
+        Err(error) => return database_error(error),
+    };
+    if accepts(&headers, "text/plain") {
+        return Response::builder()
+            .status(StatusCode::OK)
+            .header(CONTENT_TYPE, "text/plain; charset=utf-8")
+            .body(String::from(&question))
+            .unwrap();
+    }
+    json_api::negotiate(
+        &headers,
+        Response::builder()
+            .status(StatusCode::OK)
+            .body(serde_json::to_string_pretty(&question).unwrap())
+            .unwrap(),
+    )
+}
+
 /// Entry point for the web server
 pub async fn get_entry_point() -> impl IntoResponse {
     Response::builder()
@@ -34,3 +96,51 @@ pub async fn get_entry_point() -> impl IntoResponse {
         .body("Welcome to the questions and answers service by Nathan Moes!".to_string())
         .unwrap()
 }
+
+/// Liveness probe, OK as long as the process is up and serving requests
+pub async fn get_livez() -> impl IntoResponse {
+    Response::builder()
+        .status(StatusCode::OK)
+        .body("ok".to_string())
+        .unwrap()
+}
+
+/// Readiness probe, only OK when the database can be reached
+pub async fn get_readyz(State(state): State<AppState>) -> impl IntoResponse {
+    match state.ping().await {
+        Ok(_) => Response::builder()
+            .status(StatusCode::OK)
+            .body("ready".to_string())
+            .unwrap(),
+        Err(error) => {
+            tracing::event!(tracing::Level::ERROR, "readiness check failed: {:?}", error);
+            Response::builder()
+                .status(StatusCode::SERVICE_UNAVAILABLE)
+                .body("database unavailable".to_string())
+                .unwrap()
+        }
+    }
+}
+
+/// Profanity API health, 503 once the latest profanity checks have all failed
+///
+/// While the API is down posts that need a check fail with a 500, or with PROFANITY_FAIL_OPEN on are stored uncensored, this tells ops why
+pub async fn get_profanity_health(State(state): State<AppState>) -> impl IntoResponse {
+    let status = state.5.status();
+    if !status.available {
+        tracing::event!(
+            tracing::Level::WARN,
+            "profanity API degraded, {} recent failures",
+            status.recent_failures
+        );
+    }
+    Response::builder()
+        .status(if status.available {
+            StatusCode::OK
+        } else {
+            StatusCode::SERVICE_UNAVAILABLE
+        })
+        .header(CONTENT_TYPE, "application/json")
+        .body(serde_json::to_string_pretty(&status).unwrap())
+        .unwrap()
+}
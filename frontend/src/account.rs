@@ -0,0 +1,185 @@
+use crate::components::toast::error_message;
+use crate::*;
+use gloo_net::http::Request;
+use serde::{Deserialize, Serialize};
+use web_sys::{window, HtmlInputElement};
+
+/// The account of the logged in user, as returned by the backend /me endpoint
+#[derive(Debug, Clone, Deserialize, PartialEq, Eq)]
+pub struct AccountInfo {
+    pub id: i32,
+    pub email: String,
+}
+
+/// The body sent to the backend to update an account
+#[derive(Serialize)]
+struct AccountData {
+    id: i32,
+    email: String,
+    password: String,
+}
+
+/// A function component for the account page, where the logged in user can change their
+/// password or delete their account
+#[function_component(AccountPage)]
+pub fn account_page() -> Html {
+    let history = use_history().unwrap();
+    let account = use_state(|| None::<AccountInfo>);
+    let password = use_state(String::new);
+    let feedback = use_state(|| None::<Result<String, String>>);
+
+    {
+        let account = account.clone();
+        let feedback = feedback.clone();
+        let history = history.clone();
+        use_effect_with_deps(
+            move |_| {
+                match auth::token() {
+                    Some(token) => wasm_bindgen_futures::spawn_local(async move {
+                        let response = Request::get("http://localhost:8000/me")
+                            .header("Authorization", &format!("Bearer {}", token))
+                            .send()
+                            .await;
+                        match response {
+                            Ok(response) if response.ok() => {
+                                match response.json::<AccountInfo>().await {
+                                    Ok(info) => account.set(Some(info)),
+                                    Err(err) => feedback.set(Some(Err(err.to_string()))),
+                                }
+                            }
+                            Ok(response) => {
+                                if !auth::logout_if_unauthorized(&response, &history) {
+                                    feedback.set(Some(Err(error_message(response).await)));
+                                }
+                            }
+                            Err(err) => feedback.set(Some(Err(err.to_string()))),
+                        }
+                    }),
+                    None => feedback.set(Some(Err("Log in to manage your account".to_string()))),
+                }
+                || ()
+            },
+            (),
+        );
+    }
+
+    let onsubmit = {
+        let account = account.clone();
+        let password = password.clone();
+        let feedback = feedback.clone();
+        let history = history.clone();
+
+        Callback::from(move |e: FocusEvent| {
+            e.prevent_default();
+
+            let (account, token) = match ((*account).clone(), auth::token()) {
+                (Some(account), Some(token)) => (account, token),
+                _ => return,
+            };
+            let account_data = AccountData {
+                id: account.id,
+                email: account.email.clone(),
+                password: password.to_string(),
+            };
+            let feedback = feedback.clone();
+            let history = history.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                // The backend only changes the account the token belongs to, a 401 means the session ran out
+                let request = Request::put("http://localhost:8000/accounts")
+                    .query([("email", &account.email)])
+                    .header("Authorization", &format!("Bearer {}", token))
+                    .json(&account_data)
+                    .unwrap();
+
+                match request.send().await {
+                    Ok(response) if response.ok() => {
+                        feedback.set(Some(Ok("Password updated".to_string())))
+                    }
+                    Ok(response) => {
+                        if !auth::logout_if_unauthorized(&response, &history) {
+                            feedback.set(Some(Err(error_message(response).await)));
+                        }
+                    }
+                    Err(err) => feedback.set(Some(Err(err.to_string()))),
+                }
+            });
+        })
+    };
+
+    let ondelete = {
+        let account = account.clone();
+        let feedback = feedback.clone();
+
+        Callback::from(move |_: MouseEvent| {
+            let (account, token) = match ((*account).clone(), auth::token()) {
+                (Some(account), Some(token)) => (account, token),
+                _ => return,
+            };
+            let confirmed = window()
+                .and_then(|window| {
+                    window
+                        .confirm_with_message("Delete your account? This cannot be undone.")
+                        .ok()
+                })
+                .unwrap_or(false);
+            if !confirmed {
+                return;
+            }
+            let feedback = feedback.clone();
+            let history = history.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                let request = Request::delete("http://localhost:8000/accounts")
+                    .query([("email", &account.email)])
+                    .header("Authorization", &format!("Bearer {}", token));
+
+                match request.send().await {
+                    Ok(response) if response.ok() => {
+                        // The account is gone, so is the session that belonged to it
+                        auth::clear_token();
+                        history.push(Route::List);
+                    }
+                    Ok(response) => {
+                        if !auth::logout_if_unauthorized(&response, &history) {
+                            feedback.set(Some(Err(error_message(response).await)));
+                        }
+                    }
+                    Err(err) => feedback.set(Some(Err(err.to_string()))),
+                }
+            });
+        })
+    };
+
+    html! {
+        <div class="account">
+            <h1>{ "My Account" }</h1>
+            {
+                match &*feedback {
+                    Some(Ok(message)) => html! { <p class="success">{ message }</p> },
+                    Some(Err(message)) => html! { <p class="error">{ message }</p> },
+                    None => html! {},
+                }
+            }
+            {
+                if let Some(account) = &*account {
+                    html! {
+                        <>
+                            <p>{ format!("Logged in as {}", account.email) }</p>
+                            <form class="question-form" onsubmit={onsubmit}>
+                                <div class="form-group">
+                                    <label for="password">{ "New Password:" }</label>
+                                    <input id="password" type="password" class="form-input" oninput={move |e: InputEvent| password.set(e.target_unchecked_into::<HtmlInputElement>().value())} />
+                                </div>
+                                <button type="submit" class="submit-button">{ "Change Password" }</button>
+                            </form>
+                            <button class="delete-button" onclick={ondelete}>{ "Delete Account" }</button>
+                        </>
+                    }
+                } else {
+                    html! {}
+                }
+            }
+        </div>
+    }
+}
@@ -19,11 +19,10 @@ pub struct QuestionFormProps {
     pub question_id: Option<u32>,
 }
 
-/// An answer struct to represent an answer in the database
+/// The body for posting an answer, the question it answers is in the path
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
-pub struct Answer {
+pub struct NewAnswer {
     pub content: String,
-    pub question_id: u32,
 }
 
 /// A function component form for submitting a new question
@@ -40,15 +39,20 @@ pub fn question_form(&QuestionFormProps { question_id }: &QuestionFormProps) ->
             e.prevent_default();
 
             let history_clone_for_async = history_clone.clone();
-            let answer_data = Answer {
+            let answer_data = NewAnswer {
                 content: content.to_string(),
-                question_id: question_id.unwrap_or_default(),
             };
 
             wasm_bindgen_futures::spawn_local(async move {
-                let request = Request::post("http://localhost:8000/answers")
-                    .json(&answer_data)
-                    .unwrap();
+                let mut request = Request::post(&format!(
+                    "http://localhost:8000/questions/{}/answers",
+                    question_id.unwrap_or_default()
+                ));
+                // Send the token along so the answer is attributed to the logged in user
+                if let Some(token) = auth::token() {
+                    request = request.header("Authorization", &format!("Bearer {}", token));
+                }
+                let request = request.json(&answer_data).unwrap();
 
                 let response = request.send().await;
                 match response {
@@ -59,7 +63,8 @@ pub fn question_form(&QuestionFormProps { question_id }: &QuestionFormProps) ->
                                 id: question_id.unwrap_or_default(),
                             });
                             web_sys::console::log_1(&"Answer submitted successfully".into());
-                        } else {
+                        } else if !auth::logout_if_unauthorized(&response, &history_clone_for_async)
+                        {
                             let error_message = response
                                 .text()
                                 .await
@@ -0,0 +1,39 @@
+use crate::Route;
+use gloo_net::http::Response;
+use web_sys::window;
+use yew_router::history::{AnyHistory, History};
+
+/// The localStorage key the JWT from the backend login is kept under
+pub const TOKEN_KEY: &str = "token";
+
+/// Function to get the stored JWT, if the user has logged in
+pub fn token() -> Option<String> {
+    window()?.local_storage().ok()??.get_item(TOKEN_KEY).ok()?
+}
+
+/// Function to store the JWT handed out by the backend login
+pub fn set_token(token: &str) {
+    if let Some(storage) = window().and_then(|window| window.local_storage().ok().flatten()) {
+        let _ = storage.set_item(TOKEN_KEY, token);
+    }
+}
+
+/// Function to forget the stored JWT, logging the user out
+pub fn clear_token() {
+    if let Some(storage) = window().and_then(|window| window.local_storage().ok().flatten()) {
+        let _ = storage.remove_item(TOKEN_KEY);
+    }
+}
+
+/// Function to log the user out and send them to the login page when the backend answered 401,
+/// which is what an expired or otherwise invalid token gets
+///
+/// Returns true if the response was a 401 and the user was sent to log in again
+pub fn logout_if_unauthorized(response: &Response, history: &AnyHistory) -> bool {
+    if response.status() != 401 {
+        return false;
+    }
+    clear_token();
+    history.push(Route::Login);
+    true
+}
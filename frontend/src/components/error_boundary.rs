@@ -0,0 +1,40 @@
+use std::panic;
+use yew::prelude::*;
+
+/// Id of the element the error boundary renders its children into
+const BOUNDARY_ID: &str = "error-boundary";
+
+/// What is shown in place of the page once rendering it has failed, an empty href reloads the current page
+const FALLBACK: &str = r#"<div class="error-boundary">
+    <h1>Something went wrong</h1>
+    <p>This page could not be shown. <a href="">Reload</a> to try again.</p>
+</div>"#;
+
+#[derive(Properties, PartialEq)]
+pub struct ErrorBoundaryProps {
+    pub children: Children,
+}
+
+/// A function component that shows a "Something went wrong" fallback in place of its children when they panic
+///
+/// A panic leaves the app unable to render again, so the fallback is written straight into the page by
+/// the hook from `install_panic_hook`. Until then the children render and navigate as usual
+#[function_component(ErrorBoundary)]
+pub fn error_boundary(props: &ErrorBoundaryProps) -> Html {
+    html! {
+        <div id={BOUNDARY_ID}>{ for props.children.iter() }</div>
+    }
+}
+
+/// Function to set the panic hook behind the error boundary, the panic still goes to the console as well
+pub fn install_panic_hook() {
+    panic::set_hook(Box::new(|info| {
+        console_error_panic_hook::hook(info);
+        if let Some(boundary) = web_sys::window()
+            .and_then(|window| window.document())
+            .and_then(|document| document.get_element_by_id(BOUNDARY_ID))
+        {
+            boundary.set_inner_html(FALLBACK);
+        }
+    }));
+}
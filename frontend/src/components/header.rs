@@ -1,4 +1,4 @@
-use crate::Route;
+use crate::{auth, Route};
 use yew::prelude::*;
 use yew_router::prelude::*;
 
@@ -11,6 +11,13 @@ pub fn header() -> Html {
                 <ul>
                     <li><Link<Route> to={Route::List}>{ "Question List" }</Link<Route>></li>
                     <li><Link<Route> to={Route::Form}>{ "New Question" }</Link<Route>></li>
+                    {
+                        if auth::token().is_some() {
+                            html! { <li><Link<Route> to={Route::Account}>{ "My Account" }</Link<Route>></li> }
+                        } else {
+                            html! { <li><Link<Route> to={Route::Login}>{ "Log In" }</Link<Route>></li> }
+                        }
+                    }
                 </ul>
             </nav>
         </header>
@@ -1,2 +1,4 @@
+pub mod error_boundary;
 pub mod footer;
 pub mod header;
+pub mod toast;
@@ -0,0 +1,110 @@
+use gloo_net::http::Response;
+use gloo_timers::callback::Timeout;
+use serde::Deserialize;
+use yew::prelude::*;
+
+/// How long a toast stays up before it dismisses itself
+const TOAST_DURATION_MS: u32 = 4_000;
+
+/// Whether a toast reports something that worked or something that failed
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ToastKind {
+    Success,
+    Error,
+}
+
+/// A short message shown to the user for a few seconds
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct Toast {
+    pub kind: ToastKind,
+    pub message: String,
+}
+
+/// Handle to show toasts with, handed to every component below the `ToastProvider` through context
+#[derive(Clone, PartialEq)]
+pub struct Toaster(UseStateHandle<Option<Toast>>);
+
+impl Toaster {
+    /// Function to tell the user something worked
+    pub fn success(&self, message: impl Into<String>) {
+        self.show(ToastKind::Success, message.into());
+    }
+
+    /// Function to tell the user something failed
+    pub fn error(&self, message: impl Into<String>) {
+        self.show(ToastKind::Error, message.into());
+    }
+
+    fn show(&self, kind: ToastKind, message: String) {
+        self.0.set(Some(Toast { kind, message }));
+    }
+}
+
+/// Function to get the toaster from within a component below the `ToastProvider`
+pub fn use_toaster() -> Toaster {
+    use_context::<Toaster>().expect("use_toaster called outside of a ToastProvider")
+}
+
+/// The error envelope the backend answers failed requests with
+#[derive(Deserialize)]
+struct ErrorBody {
+    error: String,
+}
+
+/// Function to turn a failed response into the message to show the user
+pub async fn error_message(response: Response) -> String {
+    match response.text().await {
+        Ok(body) => serde_json::from_str::<ErrorBody>(&body)
+            .map(|body| body.error)
+            .unwrap_or(body),
+        Err(_) => "Unknown error".to_string(),
+    }
+}
+
+#[derive(Properties, PartialEq)]
+pub struct ToastProviderProps {
+    pub children: Children,
+}
+
+/// A function component that provides the toaster to its children and shows the current toast
+#[function_component(ToastProvider)]
+pub fn toast_provider(props: &ToastProviderProps) -> Html {
+    let toast = use_state(|| None::<Toast>);
+
+    {
+        let toast = toast.clone();
+        use_effect_with_deps(
+            move |current: &Option<Toast>| {
+                // Dropping the timeout cancels it, so a newer toast gets its full time on screen
+                let timeout = current
+                    .is_some()
+                    .then(|| Timeout::new(TOAST_DURATION_MS, move || toast.set(None)));
+                move || drop(timeout)
+            },
+            (*toast).clone(),
+        );
+    }
+
+    let ondismiss = {
+        let toast = toast.clone();
+        Callback::from(move |_: MouseEvent| toast.set(None))
+    };
+
+    html! {
+        <ContextProvider<Toaster> context={Toaster(toast.clone())}>
+            { for props.children.iter() }
+            {
+                match &*toast {
+                    Some(Toast { kind, message }) => {
+                        let class = match kind {
+                            ToastKind::Success => "toast success",
+                            ToastKind::Error => "toast error",
+                        };
+                        html! { <div class={class} role="status" onclick={ondismiss}>{ message }</div> }
+                    }
+                    None => html! {},
+                }
+            }
+        </ContextProvider<Toaster>>
+    }
+}
@@ -0,0 +1,91 @@
+use crate::*;
+use gloo_net::http::Request;
+use serde::{Deserialize, Serialize};
+use web_sys::HtmlInputElement;
+
+/// The credentials sent to the backend login endpoint
+#[derive(Serialize)]
+struct LoginData {
+    client_id: String,
+    client_secret: String,
+}
+
+/// The body the backend login endpoint answers with
+#[derive(Deserialize)]
+struct AuthBody {
+    access_token: String,
+}
+
+#[derive(Properties, PartialEq)]
+pub struct LoginProps {
+    /// Where to go once logged in, the page that asked for the login
+    #[prop_or_default]
+    pub redirect: Option<Route>,
+}
+
+/// A function component form for logging in, storing the token from the backend
+#[function_component(Login)]
+pub fn login(&LoginProps { redirect }: &LoginProps) -> Html {
+    let history = use_history().unwrap();
+    let client_id = use_state(String::new);
+    let client_secret = use_state(String::new);
+    let error = use_state(|| None::<String>);
+
+    let onsubmit = {
+        let client_id = client_id.clone();
+        let client_secret = client_secret.clone();
+        let error = error.clone();
+
+        Callback::from(move |e: FocusEvent| {
+            e.prevent_default();
+
+            let login_data = LoginData {
+                client_id: (*client_id).clone(),
+                client_secret: (*client_secret).clone(),
+            };
+            let history = history.clone();
+            let error = error.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                let request = Request::post("http://localhost:8000/login")
+                    .json(&login_data)
+                    .unwrap();
+
+                match request.send().await {
+                    Ok(response) if response.ok() => match response.json::<AuthBody>().await {
+                        Ok(body) => {
+                            auth::set_token(&body.access_token);
+                            history.push(redirect.unwrap_or(Route::List));
+                        }
+                        Err(err) => error.set(Some(err.to_string())),
+                    },
+                    Ok(_) => error.set(Some("Wrong credentials".to_string())),
+                    Err(err) => error.set(Some(err.to_string())),
+                }
+            });
+        })
+    };
+
+    html! {
+        <form class="question-form" onsubmit={onsubmit}>
+            <h1>{ "Log In" }</h1>
+            {
+                if let Some(error) = &*error {
+                    html! { <p class="error">{ error }</p> }
+                } else {
+                    html! {}
+                }
+            }
+            <div class="form-group">
+                <label for="client_id">{ "Username:" }</label>
+                <input type="text" id="client_id" class="form-input" oninput={move |e: InputEvent| client_id.set(e.target_unchecked_into::<HtmlInputElement>().value())} />
+            </div>
+            <div class="form-group">
+                <label for="client_secret">{ "Password:" }</label>
+                <input type="password" id="client_secret" class="form-input" oninput={move |e: InputEvent| client_secret.set(e.target_unchecked_into::<HtmlInputElement>().value())} />
+            </div>
+            <button type="submit" class="submit-button">{ "Log In" }</button>
+            <p>{ "No account yet? " }<Link<Route> to={Route::Register}>{ "Register" }</Link<Route>></p>
+        </form>
+    }
+}
@@ -1,24 +1,32 @@
 // use web_sys::{HtmlInputElement, SubmitEvent};
 #![allow(clippy::let_unit_value)]
-use console_error_panic_hook::set_once as set_panic_hook;
 use console_log::init_with_level;
 use log::Level;
 use yew::prelude::*;
 use yew_router::{prelude::*, RenderFn};
+mod account;
 mod answer_add;
+mod auth;
 mod components;
+mod login;
 mod question;
 mod question_form;
 mod question_list;
 mod question_update;
+mod register;
 
+use account::AccountPage;
 use answer_add::AnswerAdd;
+use components::error_boundary::{install_panic_hook, ErrorBoundary};
 use components::footer::Footer;
 use components::header::Header;
+use components::toast::ToastProvider;
+use login::Login;
 use question::QuestionItem;
 use question_form::QuestionForm as Form;
 use question_list::QuestionList as List;
 use question_update::{QuestionFormProps, QuestionUpdate as Update};
+use register::Register;
 
 /// The routes for the application
 #[derive(Clone, Routable, PartialEq, Debug, Copy)]
@@ -33,11 +41,25 @@ enum Route {
     Update { id: u32 },
     #[at("/answer/:id")]
     Answer { id: u32 },
+    #[at("/account")]
+    Account,
+    #[at("/login")]
+    Login,
+    #[at("/register")]
+    Register,
     #[not_found]
     #[at("/404")]
     NotFound,
 }
 
+/// Function to check if a route needs a logged in user, everything else stays public
+fn requires_login(route: &Route) -> bool {
+    matches!(
+        route,
+        Route::Form | Route::Update { .. } | Route::Answer { .. } | Route::Account
+    )
+}
+
 /// A component that displays a 404 page
 #[function_component(NotFound)]
 pub fn not_found() -> Html {
@@ -54,36 +76,46 @@ pub fn not_found() -> Html {
 fn app() -> Html {
     html! {
         <BrowserRouter>
-            <Header />
-            <Switch<Route> render={RenderFn::new(move |route: &Route| {
-                log::info!("Matched route: {:?}", route);
-                match route {
-                    Route::List => html! { <List /> },
-                    Route::Form => html! { <Form /> },
-                    Route::Update { id } => {
-                        let props = QuestionFormProps {
-                            question_id: Some(*id),
-                        };
-                        html! { <Update ..props /> }
-                    }
-                    Route::Question { id } => html! { <QuestionItem question_id={*id} /> },
-                    Route::Answer { id } => {
-                        let props = answer_add::QuestionFormProps {
-                            question_id: Some(*id),
-                        };
-                        html! { <AnswerAdd ..props /> }
-                    }
-                    Route::NotFound => html! { <NotFound /> },
-                }
-            })} />
-            <Footer />
+            <ToastProvider>
+                <Header />
+                <ErrorBoundary>
+                    <Switch<Route> render={RenderFn::new(move |route: &Route| {
+                        log::info!("Matched route: {:?}", route);
+                        if requires_login(route) && auth::token().is_none() {
+                            return html! { <Login redirect={Some(*route)} /> };
+                        }
+                        match route {
+                            Route::List => html! { <List /> },
+                            Route::Form => html! { <Form /> },
+                            Route::Update { id } => {
+                                let props = QuestionFormProps {
+                                    question_id: Some(*id),
+                                };
+                                html! { <Update ..props /> }
+                            }
+                            Route::Question { id } => html! { <QuestionItem question_id={*id} /> },
+                            Route::Answer { id } => {
+                                let props = answer_add::QuestionFormProps {
+                                    question_id: Some(*id),
+                                };
+                                html! { <AnswerAdd ..props /> }
+                            }
+                            Route::Account => html! { <AccountPage /> },
+                            Route::Login => html! { <Login /> },
+                            Route::Register => html! { <Register /> },
+                            Route::NotFound => html! { <NotFound /> },
+                        }
+                    })} />
+                </ErrorBoundary>
+                <Footer />
+            </ToastProvider>
         </BrowserRouter>
     }
 }
 
 /// The main function
 fn main() {
-    set_panic_hook();
+    install_panic_hook();
     init_with_level(Level::Info).expect("Failed to initialize logger");
     yew::start_app::<App>();
 }
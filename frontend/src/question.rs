@@ -12,6 +12,25 @@ pub struct Question {
     pub content: String,
     #[serde(default)]
     pub tags: Option<HashSet<String>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub duplicate_of: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub close_reason: Option<String>,
+}
+
+/// The characters a tag input is split on
+pub const TAG_DELIMITERS: &[char] = &[',', ' ', ';'];
+
+/// Function to turn what was typed in a tags input into a set of tags
+///
+/// Splits on any of `TAG_DELIMITERS`, lowercases each tag and drops the empty ones,
+/// so `"Rust, cargo;toml,"` gives `rust`, `cargo` and `toml`
+pub fn parse_tags(input: &str) -> HashSet<String> {
+    input
+        .split(TAG_DELIMITERS)
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect()
 }
 
 #[derive(Properties, PartialEq)]
@@ -23,8 +42,76 @@ pub struct QuestionFormProps {
 /// An answer struct to represent an answer in the database
 #[derive(Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Answer {
+    #[serde(default)]
+    pub id: Option<u32>,
     pub content: String,
     pub question_id: u32,
+    #[serde(default)]
+    pub parent_answer_id: Option<u32>,
+    #[serde(default)]
+    pub is_accepted: bool,
+}
+
+/// A question along with its answers, as returned by the backend /question/answers endpoint
+#[derive(Deserialize)]
+pub struct QuestionWithAnswers {
+    #[serde(flatten)]
+    pub question: Question,
+    pub answers: Vec<Answer>,
+    #[serde(default)]
+    pub answered_by_me: bool,
+}
+
+/// Function to render the answers replying to `parent`, with each answer's own replies nested under it
+///
+/// The accepted answer is highlighted and listed before the others
+fn render_answers(answers: &[Answer], parent: Option<u32>, on_delete: fn(u32)) -> Html {
+    let mut replies: Vec<&Answer> = answers
+        .iter()
+        .filter(|answer| answer.parent_answer_id == parent)
+        .collect();
+    // Stable sort, so the other answers keep the order the backend sent them in
+    replies.sort_by_key(|answer| !answer.is_accepted);
+    replies
+        .into_iter()
+        .map(|answer| {
+            html! {
+                <div class={classes!("answer", answer.is_accepted.then_some("accepted"))}>
+                    {
+                        if answer.is_accepted {
+                            html! { <span class="accepted-badge">{ "Accepted" }</span> }
+                        } else {
+                            html! {}
+                        }
+                    }
+                    <div class="content">{ &answer.content }
+                        <div class="actions">
+                            // <button>{ "Edit" }</button>
+                            {
+                                // An answer the backend sent without its id can not be picked out to delete
+                                if let Some(id) = answer.id {
+                                    html! {
+                                        <button  onclick={move |_|{
+                                            on_delete(id);
+                                        }}>{ "Delete" }</button>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+                        </div>
+                    </div>
+                    {
+                        if answer.id.is_some() {
+                            html! { <div class="replies">{ render_answers(answers, answer.id, on_delete) }</div> }
+                        } else {
+                            html! {}
+                        }
+                    }
+                </div>
+            }
+        })
+        .collect::<Html>()
 }
 
 /// A function component that displays a list of questions from the server backend. With a start end end parameter, it can also display a single question. By default it will only display one at the moment
@@ -33,6 +120,7 @@ pub fn question(&QuestionFormProps { question_id }: &QuestionFormProps) -> Html
     let question = use_state(|| None);
     let history = use_history().unwrap();
     let answers = use_state(Vec::<Answer>::new);
+    let answered_by_me = use_state(|| false);
 
     fn handle_delete_question(id: u32) {
         wasm_bindgen_futures::spawn_local(async move {
@@ -75,40 +163,28 @@ pub fn question(&QuestionFormProps { question_id }: &QuestionFormProps) -> Html
     {
         let question = question.clone();
         let answers = answers.clone();
+        let answered_by_me = answered_by_me.clone();
 
         use_effect_with_deps(
             move |_| {
                 let question = question.clone();
                 let answers = answers.clone();
+                let answered_by_me = answered_by_me.clone();
                 let id = question_id.unwrap_or_default();
 
                 wasm_bindgen_futures::spawn_local(async move {
-                    let request = Request::get(&format!(
-                        "http://localhost:8000/questions?start={}&end={}",
-                        id, id
-                    ))
-                    .send()
-                    .await;
-                    match request {
+                    let mut request =
+                        Request::get(&format!("http://localhost:8000/question/answers?id={}", id));
+                    // Send the token along so the backend can tell if we already answered
+                    if let Some(token) = auth::token() {
+                        request = request.header("Authorization", &format!("Bearer {}", token));
+                    }
+                    match request.send().await {
                         Ok(response) => {
-                            let questions_data: Vec<Question> =
-                                response.json().await.unwrap_or_default();
-                            if let Some(question_data) = questions_data.first() {
-                                question.set(Some(question_data.clone()));
-                            }
-
-                            let request =
-                                Request::get(&format!("http://localhost:8000/answers?id={}", id));
-                            let response = request.send().await;
-                            match response {
-                                Ok(response) => {
-                                    let answers_data: Vec<Answer> =
-                                        response.json().await.unwrap_or_default();
-                                    answers.set(answers_data);
-                                }
-                                Err(err) => {
-                                    eprintln!("Error fetching answers: {}", err);
-                                }
+                            if let Ok(bundle) = response.json::<QuestionWithAnswers>().await {
+                                question.set(Some(bundle.question));
+                                answers.set(bundle.answers);
+                                answered_by_me.set(bundle.answered_by_me);
                             }
                         }
                         Err(err) => {
@@ -130,9 +206,31 @@ pub fn question(&QuestionFormProps { question_id }: &QuestionFormProps) -> Html
                     let id = question.id;
                     let history = history.clone();
                     let history2 = history.clone();
+                    let answered_by_me = *answered_by_me;
                     html! {
                         <div class="question">
                             <h2 class="title">{ &question.title }</h2>
+                            {
+                                if let Some(duplicate_of) = question.duplicate_of {
+                                    let history = history.clone();
+                                    html! {
+                                        <div class="duplicate" onclick={move |_| {
+                                            history.push(Route::Question{id: duplicate_of});
+                                        }}>{ format!("Marked as duplicate of #{}", duplicate_of) }</div>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
+                            {
+                                if let Some(close_reason) = &question.close_reason {
+                                    html! {
+                                        <div class="closed">{ format!("Closed as {}, no new answers can be posted", close_reason) }</div>
+                                    }
+                                } else {
+                                    html! {}
+                                }
+                            }
                             <div class="content">{ &question.content }</div>
                             <div class="tags">{
                                 question.tags.as_ref().map(|tags| {
@@ -148,9 +246,9 @@ pub fn question(&QuestionFormProps { question_id }: &QuestionFormProps) -> Html
                                 <button onclick={move |_| {
                                     handle_delete_question(id);
                                 }}>{ "Delete" }</button>
-                                <button onclick={move |_| {
+                                <button disabled={answered_by_me} onclick={move |_| {
                                     history2.push(Route::Answer{id});
-                                }}>{ "Add Answer" }</button>
+                                }}>{ if answered_by_me { "Already Answered" } else { "Add Answer" } }</button>
                             </div>
                         </div>
                     }
@@ -161,25 +259,7 @@ pub fn question(&QuestionFormProps { question_id }: &QuestionFormProps) -> Html
 
             <h3>{ "Answers" }</h3>
             <div class="answer-list">
-                {
-                    answers.iter().map(|answer| {
-                        let id = answer.question_id;
-                        html! {
-                            <>
-                                <div class="answer">
-                                    <div class="content">{ &answer.content }
-                                        <div class="actions">
-                                            // <button>{ "Edit" }</button>
-                                            <button  onclick={move |_|{
-                                                handle_delete_answer(id);
-                                            }}>{ "Delete" }</button>
-                                        </div>
-                                    </div>
-                                </div>
-                            </>
-                        }
-                    }).collect::<Html>()
-                }
+                { render_answers(answers.as_slice(), None, handle_delete_answer) }
             </div>
         </>
     }
@@ -1,9 +1,11 @@
 use std::collections::HashSet;
 
+use crate::components::toast::{error_message, use_toaster};
+use crate::question::parse_tags;
 use crate::*;
 use gloo_net::http::Request;
-use serde::Serialize;
-use web_sys::HtmlInputElement;
+use serde::{Deserialize, Serialize};
+use web_sys::{window, HtmlInputElement, HtmlSelectElement};
 
 #[derive(Serialize)]
 struct QuestionData {
@@ -11,29 +13,54 @@ struct QuestionData {
     title: String,
     content: String,
     tags: Option<HashSet<String>>,
+    category: String,
 }
 
+/// A question the backend thinks the new one may duplicate
+#[derive(Deserialize)]
+struct SimilarQuestion {
+    title: String,
+    #[serde(default)]
+    answer_count: i64,
+}
+
+/// The warning the backend sends back instead of adding a question with a near duplicate title
+#[derive(Deserialize)]
+struct SimilarQuestionsWarning {
+    warning: String,
+    similar: Vec<SimilarQuestion>,
+}
+
+/// The question categories the backend accepts, as (value, label) pairs
+const CATEGORIES: [(&str, &str); 4] = [
+    ("general", "General"),
+    ("debugging", "Debugging"),
+    ("concept", "Concept"),
+    ("tooling", "Tooling"),
+];
+
 /// A function component form for submitting a new question
 #[function_component(QuestionForm)]
 pub fn question_form() -> Html {
     let history = use_history().unwrap();
+    let toaster = use_toaster();
     let title = use_state(String::new);
     let content = use_state(String::new);
     let tags = use_state(String::new);
+    let category = use_state(|| "general".to_string());
 
     let onsubmit = {
         let title = title.clone();
         let content = content.clone();
         let tags = tags.clone();
+        let category = category.clone();
         let history_clone = history.clone();
+        let toaster = toaster.clone();
 
         Callback::from(move |e: FocusEvent| {
             e.prevent_default();
 
-            let tags_set = tags
-                .split(',')
-                .map(|tag| tag.trim().to_string())
-                .collect::<HashSet<String>>();
+            let tags_set = parse_tags(&tags);
 
             let question_data = QuestionData {
                 id: 0,
@@ -44,33 +71,77 @@ pub fn question_form() -> Html {
                 } else {
                     Some(tags_set.iter().cloned().collect::<HashSet<String>>())
                 },
+                category: (*category).clone(),
             };
 
             let history_clone_for_async = history_clone.clone();
+            let toaster = toaster.clone();
 
             wasm_bindgen_futures::spawn_local(async move {
-                let request = Request::post("http://localhost:8000/questions")
-                    .json(&question_data)
-                    .unwrap();
+                let mut force = false;
+                loop {
+                    let url = if force {
+                        "http://localhost:8000/questions?force=true"
+                    } else {
+                        "http://localhost:8000/questions"
+                    };
+                    let mut request = Request::post(url);
+                    // Send the token along so the question is attributed to the logged in user
+                    if let Some(token) = auth::token() {
+                        request = request.header("Authorization", &format!("Bearer {}", token));
+                    }
+                    let request = request.json(&question_data).unwrap();
 
-                let response = request.send().await;
-                match response {
-                    Ok(response) => {
-                        if response.ok() {
-                            // Success, redirect to main page/list page
-                            history_clone_for_async.push(Route::List);
-                            web_sys::console::log_1(&"Question submitted successfully".into());
-                        } else {
-                            let error_message = response
-                                .text()
-                                .await
-                                .unwrap_or_else(|_| "Unknown error".to_string());
-                            web_sys::console::error_1(&error_message.into());
+                    let response = request.send().await;
+                    match response {
+                        Ok(response) => {
+                            if response.ok() {
+                                // The question was held back as a likely duplicate, let the user decide
+                                if let Ok(warning) =
+                                    response.json::<SimilarQuestionsWarning>().await
+                                {
+                                    let titles = warning
+                                        .similar
+                                        .iter()
+                                        .map(|question| {
+                                            format!(
+                                                "- {} ({} answers)",
+                                                question.title, question.answer_count
+                                            )
+                                        })
+                                        .collect::<Vec<String>>()
+                                        .join("\n");
+                                    let post_anyway = window()
+                                        .and_then(|window| {
+                                            window
+                                                .confirm_with_message(&format!(
+                                                    "{}\n\n{}\n\nPost anyway?",
+                                                    warning.warning, titles
+                                                ))
+                                                .ok()
+                                        })
+                                        .unwrap_or(false);
+                                    if post_anyway && !force {
+                                        force = true;
+                                        continue;
+                                    }
+                                    break;
+                                }
+                                // Success, redirect to main page/list page
+                                history_clone_for_async.push(Route::List);
+                                toaster.success("Question created");
+                            } else if !auth::logout_if_unauthorized(
+                                &response,
+                                &history_clone_for_async,
+                            ) {
+                                toaster.error(error_message(response).await);
+                            }
+                        }
+                        Err(err) => {
+                            toaster.error(err.to_string());
                         }
                     }
-                    Err(err) => {
-                        web_sys::console::error_1(&err.to_string().into());
-                    }
+                    break;
                 }
             });
         })
@@ -87,9 +158,19 @@ pub fn question_form() -> Html {
                 <textarea id="content" class="form-textarea" oninput={move |e: InputEvent| content.set(e.target_unchecked_into::<HtmlInputElement>().value())}></textarea>
             </div>
             <div class="form-group">
-                <label for="tags">{ "Tags (comma-separated):" }</label>
+                <label for="tags">{ "Tags (separated by commas, spaces or semicolons):" }</label>
                 <input type="text" id="tags" class="form-input" oninput={move |e: InputEvent| tags.set(e.target_unchecked_into::<HtmlInputElement>().value())} />
             </div>
+            <div class="form-group">
+                <label for="category">{ "Category:" }</label>
+                <select id="category" class="form-input" onchange={move |e: Event| category.set(e.target_unchecked_into::<HtmlSelectElement>().value())}>
+                    {
+                        CATEGORIES.iter().map(|(value, label)| html! {
+                            <option value={*value} selected={*value == "general"}>{ *label }</option>
+                        }).collect::<Html>()
+                    }
+                </select>
+            </div>
             <button type="submit" class="submit-button">{ "Submit" }</button>
         </form>
     }
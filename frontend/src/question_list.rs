@@ -1,9 +1,9 @@
 use std::collections::HashSet;
 
+use crate::components::toast::{error_message, use_toaster, Toaster};
 use crate::*;
 use gloo_net::http::Request;
 use serde::{Deserialize, Serialize};
-use web_sys::window;
 
 #[derive(Deserialize, Clone, PartialEq, Serialize)]
 pub struct Question {
@@ -12,6 +12,14 @@ pub struct Question {
     pub content: String,
     #[serde(default)]
     pub tags: Option<HashSet<String>>,
+    #[serde(default)]
+    pub answer_count: i64,
+    #[serde(default)]
+    pub has_accepted_answer: bool,
+    #[serde(default)]
+    pub pinned: bool,
+    #[serde(default)]
+    pub duplicate_of: Option<u32>,
 }
 
 /// A function component that displays a list of questions from the server backend
@@ -19,8 +27,9 @@ pub struct Question {
 pub fn question_form() -> Html {
     let questions = use_state(Vec::<Question>::new);
     let history = use_history().unwrap();
+    let toaster = use_toaster();
 
-    fn handle_delete_question(id: u32) {
+    fn handle_delete_question(id: u32, questions: UseStateHandle<Vec<Question>>, toaster: Toaster) {
         wasm_bindgen_futures::spawn_local(async move {
             let request = Request::delete(&format!("http://localhost:8000/questions?id={}", id))
                 .send()
@@ -28,12 +37,22 @@ pub fn question_form() -> Html {
             match request {
                 Ok(response) => {
                     if response.ok() {
-                        // Success, refresh the list of questions
-                        window().unwrap().location().reload().unwrap();
+                        // Success, drop the question from the list rather than reloading,
+                        // so the toast stays on screen
+                        questions.set(
+                            questions
+                                .iter()
+                                .filter(|question| question.id != id)
+                                .cloned()
+                                .collect(),
+                        );
+                        toaster.success("Deleted");
+                    } else {
+                        toaster.error(error_message(response).await);
                     }
                 }
                 Err(err) => {
-                    eprintln!("Error deleting question: {}", err);
+                    toaster.error(err.to_string());
                 }
             }
         });
@@ -47,7 +66,9 @@ pub fn question_form() -> Html {
                 let questions = questions.clone();
 
                 wasm_bindgen_futures::spawn_local(async move {
-                    let request = Request::get("http://localhost:8000/questions").send().await;
+                    let request = Request::get("http://localhost:8000/questions?rich=true")
+                        .send()
+                        .await;
                     match request {
                         Ok(response) => {
                             let questions_data: Vec<Question> =
@@ -75,10 +96,42 @@ pub fn question_form() -> Html {
                         let id = question.id;
                         let history = history.clone();
                         let item_history = history.clone();
+                        let questions = questions.clone();
+                        let toaster = toaster.clone();
                         html! {
                             <div class="question">
                                 <div class="id">{ question.id }</div>
-                                <div class="title">{ &question.title }</div>
+                                <div class="title">
+                                    {
+                                        if question.pinned {
+                                            html! { <span class="pinned">{ "Pinned" }</span> }
+                                        } else {
+                                            html! {}
+                                        }
+                                    }
+                                    { &question.title }
+                                </div>
+                                {
+                                    match question.duplicate_of {
+                                        Some(duplicate_of) => html! {
+                                            <div class="duplicate">{ format!("Marked as duplicate of #{}", duplicate_of) }</div>
+                                        },
+                                        None => html! {},
+                                    }
+                                }
+                                <div class="answer-count">{
+                                    match question.answer_count {
+                                        1 => "1 answer".to_string(),
+                                        count => format!("{} answers", count),
+                                    }
+                                }
+                                {
+                                    if question.has_accepted_answer {
+                                        html! { <span class="accepted-badge">{ "Accepted answer" }</span> }
+                                    } else {
+                                        html! {}
+                                    }
+                                }</div>
                                 <div class="content" onclick={move |_|{
                                     item_history.push(Route::Question{id})
                                 }}>{ &question.content }</div>
@@ -94,7 +147,7 @@ pub fn question_form() -> Html {
                                         history.push(Route::Update{id});
                                     }}>{ "Edit" }</button>
                                     <button onclick={move |_| {
-                                        handle_delete_question(id);
+                                        handle_delete_question(id, questions.clone(), toaster.clone());
                                     }}>{ "Delete" }</button>
                                 </div>
                             </div>
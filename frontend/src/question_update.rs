@@ -1,5 +1,7 @@
 use std::collections::HashSet;
 
+use crate::components::toast::{error_message, use_toaster};
+use crate::question::parse_tags;
 use crate::*;
 use gloo_net::http::Request;
 use serde::Serialize;
@@ -23,23 +25,68 @@ pub struct QuestionFormProps {
 #[function_component(QuestionUpdate)]
 pub fn question_update(&QuestionFormProps { question_id }: &QuestionFormProps) -> Html {
     let history = use_history().unwrap();
+    let toaster = use_toaster();
     let title = use_state(String::new);
     let content = use_state(String::new);
     let tags = use_state(String::new);
+    let load_error = use_state(|| None::<String>);
+
+    // Prefill the form with the question as it is now, so editing does not start from blank fields
+    {
+        let title = title.clone();
+        let content = content.clone();
+        let tags = tags.clone();
+        let load_error = load_error.clone();
+        use_effect_with_deps(
+            move |question_id: &Option<u32>| {
+                if let Some(id) = *question_id {
+                    wasm_bindgen_futures::spawn_local(async move {
+                        let response =
+                            Request::get(&format!("http://localhost:8000/questions/{}", id))
+                                .send()
+                                .await;
+                        match response {
+                            Ok(response) if response.ok() => {
+                                match response.json::<crate::question::Question>().await {
+                                    Ok(question) => {
+                                        title.set(question.title);
+                                        content.set(question.content);
+                                        tags.set(
+                                            question
+                                                .tags
+                                                .map(|tags| {
+                                                    tags.into_iter()
+                                                        .collect::<Vec<String>>()
+                                                        .join(", ")
+                                                })
+                                                .unwrap_or_default(),
+                                        );
+                                    }
+                                    Err(err) => load_error.set(Some(err.to_string())),
+                                }
+                            }
+                            Ok(response) => load_error.set(Some(error_message(response).await)),
+                            Err(err) => load_error.set(Some(err.to_string())),
+                        }
+                    });
+                }
+                || ()
+            },
+            question_id,
+        );
+    }
 
     let onsubmit = {
         let title = title.clone();
         let content = content.clone();
         let tags = tags.clone();
         let history_clone = history.clone();
+        let toaster = toaster.clone();
 
         Callback::from(move |e: FocusEvent| {
             e.prevent_default();
 
-            let tags_set = tags
-                .split(',')
-                .map(|tag| tag.trim().to_string())
-                .collect::<HashSet<String>>();
+            let tags_set = parse_tags(&tags);
 
             let question_data = QuestionData {
                 id: question_id.unwrap_or(0),
@@ -53,6 +100,7 @@ pub fn question_update(&QuestionFormProps { question_id }: &QuestionFormProps) -
             };
 
             let history_clone_for_async = history_clone.clone();
+            let toaster = toaster.clone();
 
             wasm_bindgen_futures::spawn_local(async move {
                 let url = if let Some(id) = question_id {
@@ -75,17 +123,17 @@ pub fn question_update(&QuestionFormProps { question_id }: &QuestionFormProps) -
                         if response.ok() {
                             // Success, redirect to main page/list page
                             history_clone_for_async.push(Route::List);
-                            web_sys::console::log_1(&"Question submitted successfully".into());
+                            toaster.success(if question_id.is_some() {
+                                "Question updated"
+                            } else {
+                                "Question created"
+                            });
                         } else {
-                            let error_message = response
-                                .text()
-                                .await
-                                .unwrap_or_else(|_| "Unknown error".to_string());
-                            web_sys::console::error_1(&error_message.into());
+                            toaster.error(error_message(response).await);
                         }
                     }
                     Err(err) => {
-                        web_sys::console::error_1(&err.to_string().into());
+                        toaster.error(err.to_string());
                     }
                 }
             });
@@ -94,17 +142,24 @@ pub fn question_update(&QuestionFormProps { question_id }: &QuestionFormProps) -
 
     html! {
         <form class="question-form" onsubmit={onsubmit}>
+            {
+                if let Some(error) = &*load_error {
+                    html! { <p class="error">{ format!("Could not load the question: {}", error) }</p> }
+                } else {
+                    html! {}
+                }
+            }
             <div class="form-group">
                 <label for="title">{ "Title:" }</label>
-                <input type="text" id="title" class="form-input" oninput={move |e: InputEvent| title.set(e.target_unchecked_into::<HtmlInputElement>().value())} />
+                <input type="text" id="title" class="form-input" value={(*title).clone()} oninput={let title = title.clone(); move |e: InputEvent| title.set(e.target_unchecked_into::<HtmlInputElement>().value())} />
             </div>
             <div class="form-group">
                 <label for="content">{ "Content:" }</label>
-                <textarea id="content" class="form-textarea" oninput={move |e: InputEvent| content.set(e.target_unchecked_into::<HtmlInputElement>().value())}></textarea>
+                <textarea id="content" class="form-textarea" value={(*content).clone()} oninput={let content = content.clone(); move |e: InputEvent| content.set(e.target_unchecked_into::<HtmlInputElement>().value())}></textarea>
             </div>
             <div class="form-group">
-                <label for="tags">{ "Tags (comma-separated):" }</label>
-                <input type="text" id="tags" class="form-input" oninput={move |e: InputEvent| tags.set(e.target_unchecked_into::<HtmlInputElement>().value())} />
+                <label for="tags">{ "Tags (separated by commas, spaces or semicolons):" }</label>
+                <input type="text" id="tags" class="form-input" value={(*tags).clone()} oninput={let tags = tags.clone(); move |e: InputEvent| tags.set(e.target_unchecked_into::<HtmlInputElement>().value())} />
             </div>
             <button type="submit" class="submit-button">{ if question_id.is_some() { "Update" } else { "Submit" } }</button>
         </form>
@@ -0,0 +1,126 @@
+use crate::components::toast::error_message;
+use crate::*;
+use gloo_net::http::Request;
+use serde::{Deserialize, Serialize};
+use web_sys::HtmlInputElement;
+
+/// The body sent to the backend to create an account
+#[derive(Serialize)]
+struct AccountData {
+    id: i32,
+    email: String,
+    password: String,
+}
+
+/// The body the backend account exists endpoint answers with
+#[derive(Deserialize)]
+struct AccountExists {
+    exists: bool,
+}
+
+/// A function component form for creating an account, warning as soon as the email is taken
+#[function_component(Register)]
+pub fn register() -> Html {
+    let history = use_history().unwrap();
+    let email = use_state(String::new);
+    let password = use_state(String::new);
+    let email_taken = use_state(|| false);
+    let error = use_state(|| None::<String>);
+
+    // Checked when the email field loses focus, so the form can warn before it is submitted
+    let onblur = {
+        let email = email.clone();
+        let email_taken = email_taken.clone();
+
+        Callback::from(move |_: FocusEvent| {
+            let email = email.trim().to_string();
+            let email_taken = email_taken.clone();
+            if email.is_empty() {
+                email_taken.set(false);
+                return;
+            }
+            wasm_bindgen_futures::spawn_local(async move {
+                let response = Request::get("http://localhost:8000/accounts/exists")
+                    .query([("email", &email)])
+                    .send()
+                    .await;
+                // A failed check, e.g. rate limited, is no reason to block the form, the submit says if it is taken
+                if let Ok(response) = response {
+                    if let Ok(body) = response.json::<AccountExists>().await {
+                        email_taken.set(body.exists);
+                    }
+                }
+            });
+        })
+    };
+
+    // A changed email has not been checked yet, so it no longer holds the submit back
+    let oninput = {
+        let email = email.clone();
+        let email_taken = email_taken.clone();
+
+        Callback::from(move |e: InputEvent| {
+            email.set(e.target_unchecked_into::<HtmlInputElement>().value());
+            email_taken.set(false);
+        })
+    };
+
+    let onsubmit = {
+        let email = email.clone();
+        let password = password.clone();
+        let error = error.clone();
+
+        Callback::from(move |e: FocusEvent| {
+            e.prevent_default();
+
+            let account_data = AccountData {
+                id: 0,
+                email: email.trim().to_string(),
+                password: (*password).clone(),
+            };
+            let history = history.clone();
+            let error = error.clone();
+
+            wasm_bindgen_futures::spawn_local(async move {
+                let request = Request::post("http://localhost:8000/accounts")
+                    .json(&account_data)
+                    .unwrap();
+
+                match request.send().await {
+                    Ok(response) if response.ok() => history.push(Route::Login),
+                    Ok(response) => error.set(Some(error_message(response).await)),
+                    Err(err) => error.set(Some(err.to_string())),
+                }
+            });
+        })
+    };
+
+    html! {
+        <form class="question-form" onsubmit={onsubmit}>
+            <h1>{ "Register" }</h1>
+            {
+                if let Some(error) = &*error {
+                    html! { <p class="error">{ error }</p> }
+                } else {
+                    html! {}
+                }
+            }
+            <div class="form-group">
+                <label for="email">{ "Email:" }</label>
+                <input type="email" id="email" class="form-input" onblur={onblur} oninput={oninput} />
+                {
+                    if *email_taken {
+                        html! { <p class="error">{ "An account with this email already exists" }</p> }
+                    } else {
+                        html! {}
+                    }
+                }
+            </div>
+            <div class="form-group">
+                <label for="password">{ "Password:" }</label>
+                <input type="password" id="password" class="form-input" oninput={move |e: InputEvent| password.set(e.target_unchecked_into::<HtmlInputElement>().value())} />
+            </div>
+            <button type="submit" class="submit-button" disabled={*email_taken}>{ "Register" }</button>
+        </form>
+    }
+}